@@ -58,35 +58,35 @@ pub struct CsvSpell {
     pub focus: bool,
     #[serde(deserialize_with = "bool_from_string")]
     pub divine_focus: bool,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub sor: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub wiz: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub cleric: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub druid: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub ranger: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub bard: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub paladin: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub alchemist: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub summoner: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub witch: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub inquisitor: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub oracle: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub antipaladin: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub magus: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub adept: Option<u32>,
     #[serde(deserialize_with = "csv::invalid_option")]
     pub deity: Option<String>,
@@ -154,25 +154,25 @@ pub struct CsvSpell {
     pub augmented: String,
     #[serde(deserialize_with = "bool_from_string")]
     pub mythic: bool,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub bloodrager: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub shaman: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub psychic: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub medium: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub mesmerist: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub occultist: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub spiritualist: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub skald: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub investigator: Option<u32>,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub hunter: Option<u32>,
     pub haunt_statistics: String,
     #[serde(deserialize_with = "bool_from_string")]
@@ -181,15 +181,131 @@ pub struct CsvSpell {
     pub draconic: bool,
     #[serde(deserialize_with = "bool_from_string")]
     pub meditative: bool,
-    #[serde(deserialize_with = "csv::invalid_option")]
+    #[serde(deserialize_with = "csv::invalid_option", skip_serializing)]
     pub summoner_unchained: Option<u32>,
+    #[serde(skip_deserializing)]
+    pub duration_value: SpellDuration,
+    #[serde(skip_deserializing)]
+    pub range_value: RangeValue,
+    #[serde(skip_deserializing)]
+    pub area_value: Option<SpellShape>,
+    #[serde(skip_deserializing)]
+    pub target_count: TargetCount,
+    #[serde(skip_deserializing)]
+    pub casting_time_value: CastingTime,
+    #[serde(skip_deserializing)]
+    pub thought_component: bool,
+    #[serde(skip_deserializing)]
+    pub emotion_component: bool,
+    #[serde(skip_deserializing)]
+    pub spell_levels: Vec<(Class, u32)>,
 }
 
 include!("spell-build.rs");
+include!("spell-parse.rs");
+
+impl CsvSpell {
+    /// Fills the typed fields from their text columns.
+    fn parse_fields(&mut self) {
+        self.duration_value = parse_duration(&self.duration, self.dismissible);
+        self.range_value = parse_range(&self.range);
+        self.area_value = parse_shape(&self.area).or_else(|| parse_shape(&self.effect));
+        self.target_count = parse_targets(&self.targets);
+        self.casting_time_value = parse_casting_time(&self.casting_time);
+        self.thought_component = has_component(&self.components, "thought");
+        self.emotion_component = has_component(&self.components, "emotion");
+        self.spell_levels = self.parse_spell_levels();
+    }
+
+    /// The lists of `spell_level`, completed by the class columns, the lists other classes
+    /// borrow, the granted spells of domains, bloodlines and patrons and the occultist
+    /// implements.
+    fn parse_spell_levels(&self) -> Vec<(Class, u32)> {
+        let mut levels = Vec::new();
+        // "sorcerer/wizard 3, summoner (unchained) 3, magus 3"
+        for entry in self.spell_level.split(',') {
+            let Some((names, level)) = entry.trim().rsplit_once(' ') else {
+                continue;
+            };
+            let Ok(level) = level.parse() else {
+                continue;
+            };
+            for name in names.split('/') {
+                add_level(&mut levels, Class::parse(name), level);
+            }
+        }
+
+        let columns = [
+            (Class::Sorcerer, self.sor),
+            (Class::Wizard, self.wiz),
+            (Class::Cleric, self.cleric),
+            (Class::Druid, self.druid),
+            (Class::Ranger, self.ranger),
+            (Class::Bard, self.bard),
+            (Class::Paladin, self.paladin),
+            (Class::Alchemist, self.alchemist),
+            (Class::Summoner, self.summoner),
+            (Class::Witch, self.witch),
+            (Class::Inquisitor, self.inquisitor),
+            (Class::Oracle, self.oracle),
+            (Class::Antipaladin, self.antipaladin),
+            (Class::Magus, self.magus),
+            (Class::Adept, self.adept),
+            (Class::Bloodrager, self.bloodrager),
+            (Class::Shaman, self.shaman),
+            (Class::Psychic, self.psychic),
+            (Class::Medium, self.medium),
+            (Class::Mesmerist, self.mesmerist),
+            (Class::Occultist, self.occultist),
+            (Class::Spiritualist, self.spiritualist),
+            (Class::Skald, self.skald),
+            (Class::Investigator, self.investigator),
+            (Class::Hunter, self.hunter),
+            (Class::UncSummoner, self.summoner_unchained),
+        ];
+        for (class, level) in columns {
+            if let Some(level) = level {
+                add_level(&mut levels, class, level);
+            }
+        }
+
+        // arcanists cast from the sorcerer/wizard list, warpriests have a list of their own
+        // that `spell_level` names
+        let wizard = levels.iter().find(|(c, _)| *c == Class::Wizard);
+        if let Some(&(_, level)) = wizard {
+            add_level(&mut levels, Class::Arcanist, level);
+        }
+
+        for (name, level) in granted(&self.domain) {
+            add_level(&mut levels, Class::Domain(name), level);
+        }
+        for (name, level) in granted(&self.bloodline) {
+            add_level(&mut levels, Class::Bloodline(name), level);
+        }
+        for (name, level) in granted(&self.patron) {
+            add_level(&mut levels, Class::Patron(name), level);
+        }
+        // an occultist implement gives access to the occultist spells of its school
+        let occultist = levels.iter().find(|(c, _)| *c == Class::Occultist);
+        if let Some(&(_, level)) = occultist {
+            let school = self.school.to_case(Case::Title);
+            add_level(&mut levels, Class::Implement(school), level);
+        }
+        levels
+    }
+}
+
+/// Adds a list unless the spell is already on it, the first level found wins.
+fn add_level(levels: &mut Vec<(Class, u32)>, class: Class, level: u32) {
+    if !levels.iter().any(|(c, _)| *c == class) {
+        levels.push((class, level));
+    }
+}
 
 fn main() {
     println!("cargo::rerun-if-changed=build.rs");
     println!("cargo::rerun-if-changed=spell-build.rs");
+    println!("cargo::rerun-if-changed=spell-parse.rs");
     println!("cargo::rerun-if-changed=db/spells.csv");
 
     let spells = load_spell_table();
@@ -263,7 +379,14 @@ fn load_spell_table() -> Vec<CsvSpell> {
     let mut reader = csv::ReaderBuilder::new()
         .terminator(csv::Terminator::CRLF)
         .from_reader(data.as_bytes());
-    reader.deserialize().filter_map(|x| x.ok()).collect()
+    reader
+        .deserialize()
+        .filter_map(|x| x.ok())
+        .map(|mut spell: CsvSpell| {
+            spell.parse_fields();
+            spell
+        })
+        .collect()
 }
 
 fn save_spell_table(spells: Vec<CsvSpell>) {
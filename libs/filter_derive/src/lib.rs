@@ -178,6 +178,34 @@ fn impl_filter_repr(input: &syn::DeriveInput) -> TokenStream {
                 })
                 .collect();
 
+            let names: Vec<String> = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    variant
+                        .attrs
+                        .iter()
+                        .filter_map(|a| a.meta.require_name_value().ok())
+                        .filter(|a| a.path.is_ident("name"))
+                        .filter_map(|a| match &a.value {
+                            syn::Expr::Lit(expr_lit) => match &expr_lit.lit {
+                                syn::Lit::Str(lit_str) => Some(lit_str.value()),
+                                _ => None,
+                            },
+                            _ => None,
+                        })
+                        .next()
+                        .unwrap_or(
+                            variant
+                                .ident
+                                .to_string()
+                                .trim_start_matches('_')
+                                .replace("_", " ")
+                                .to_string(),
+                        )
+                })
+                .collect();
+
             // Here we construct the function for the current variant
             variant_checker_functions.extend(quote! {
                 fn create_btn(&self, ui: &mut egui::Ui) -> Self {
@@ -220,6 +248,30 @@ fn impl_filter_repr(input: &syn::DeriveInput) -> TokenStream {
                 }
             });
 
+            variant_checker_functions.extend(quote! {
+                fn name(&self) -> &'static str {
+                    match self {
+                        #(Self::#idents (_) => #names,)*
+                    }
+                }
+            });
+
+            variant_checker_functions.extend(quote! {
+                fn state(&self) -> &FilterState {
+                    match self {
+                        #(Self::#idents (filter_state) => filter_state,)*
+                    }
+                }
+            });
+
+            variant_checker_functions.extend(quote! {
+                fn with_state(&self, state: FilterState) -> Self {
+                    match self {
+                        #(Self::#idents (_) => Self::#idents (state),)*
+                    }
+                }
+            });
+
             let expanded = quote! {
                 impl FilterRepr for #name {
                     // variant_checker_functions gets replaced by all the functions
@@ -10,6 +10,14 @@ pub trait FilterRepr {
         Self: Sized;
 
     fn some_filter(&self) -> bool;
+
+    fn name(&self) -> &'static str;
+
+    fn state(&self) -> &FilterState;
+
+    fn with_state(&self, state: FilterState) -> Self
+    where
+        Self: Sized;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
@@ -44,6 +52,15 @@ impl FilterState {
         }
     }
 
+    /// Whether a spell passes the filter given whether it has the filtered property.
+    pub fn test_match(&self, matches: bool) -> bool {
+        match self {
+            FilterState::None => true,
+            FilterState::Positive => matches,
+            FilterState::Negative => !matches,
+        }
+    }
+
     pub fn test(&self, spell: &str, value: &str) -> bool {
         match self {
             FilterState::None => true,
@@ -31,21 +31,6 @@ pub struct Spell {
     pub material: bool,
     pub focus: bool,
     pub divine_focus: bool,
-    pub sor: Option<u32>,
-    pub wiz: Option<u32>,
-    pub cleric: Option<u32>,
-    pub druid: Option<u32>,
-    pub ranger: Option<u32>,
-    pub bard: Option<u32>,
-    pub paladin: Option<u32>,
-    pub alchemist: Option<u32>,
-    pub summoner: Option<u32>,
-    pub witch: Option<u32>,
-    pub inquisitor: Option<u32>,
-    pub oracle: Option<u32>,
-    pub antipaladin: Option<u32>,
-    pub magus: Option<u32>,
-    pub adept: Option<u32>,
     pub deity: Option<String>,
     #[serde(rename = "SLA_Level")]
     pub sla_level: u32,
@@ -84,21 +69,38 @@ pub struct Spell {
     pub mythic_text: String,
     pub augmented: String,
     pub mythic: bool,
-    pub bloodrager: Option<u32>,
-    pub shaman: Option<u32>,
-    pub psychic: Option<u32>,
-    pub medium: Option<u32>,
-    pub mesmerist: Option<u32>,
-    pub occultist: Option<u32>,
-    pub spiritualist: Option<u32>,
-    pub skald: Option<u32>,
-    pub investigator: Option<u32>,
-    pub hunter: Option<u32>,
     pub haunt_statistics: String,
     pub ruse: bool,
     pub draconic: bool,
     pub meditative: bool,
-    pub summoner_unchained: Option<u32>,
+    // parsed fields default, so a spell stored by an older version still loads
+    /// `duration` parsed by the build script.
+    #[serde(default)]
+    pub duration_value: SpellDuration,
+    /// `range` parsed by the build script.
+    #[serde(default)]
+    pub range_value: RangeValue,
+    /// Geometry of `area`, or of `effect` for spells without an area.
+    #[serde(default)]
+    pub area_value: Option<SpellShape>,
+    /// `targets` parsed by the build script.
+    #[serde(default)]
+    pub target_count: TargetCount,
+    /// `casting_time` parsed by the build script.
+    #[serde(default)]
+    pub casting_time_value: CastingTime,
+    /// Psychic spells with a thought component, parsed from `components`.
+    #[serde(default)]
+    pub thought_component: bool,
+    /// Psychic spells with an emotion component, parsed from `components`. Not to be confused
+    /// with the emotion descriptor in `emotion`.
+    #[serde(default)]
+    pub emotion_component: bool,
+    /// Every spell list the spell is on with its level there, parsed from `spell_level`,
+    /// the class columns of the CSV, `domain`, `bloodline`, `patron` and, for the occultist
+    /// implements, `school` by the build script.
+    #[serde(default)]
+    pub spell_levels: Vec<(Class, u32)>,
 }
 
 impl Display for Spell {
@@ -108,44 +110,400 @@ impl Display for Spell {
 }
 
 impl Spell {
-    pub fn filter_map_level(
-        &self,
-        class_sel_or: bool,
-        classes: &[ClassType],
-    ) -> Option<(&Self, String)> {
-        let res = if class_sel_or {
-            let mut it = classes.iter().filter(|f| f.some_filter());
-            if let Some(ff) = it.next() {
-                ff.test_cls(self) || it.any(|f| f.test_cls(self))
-            } else {
-                true
-            }
-        } else {
-            classes
-                .iter()
-                .filter(|f| f.some_filter())
-                .all(|f| f.test_cls(self))
-        };
+    /// Price of the material components in gp, `None` for costly components without a
+    /// known price.
+    pub fn material_cost(&self) -> Option<u32> {
+        match self.material_costs {
+            Some(cost) => Some(cost),
+            None if self.costly_components => None,
+            None => Some(0),
+        }
+    }
+
+    /// The spell's level on the list of `class`, `None` if it isn't on it.
+    pub fn class_level(&self, class: &Class) -> Option<u32> {
+        self.spell_levels
+            .iter()
+            .find(|(c, _)| c == class)
+            .map(|(_, level)| *level)
+    }
+
+    /// The level shown in the table, the levels of the `Positive` classes separated by `/`
+    /// or the SLA level if there are none.
+    pub fn level_string(&self, classes: &[ClassType]) -> String {
         let lvls: Vec<String> = classes
             .iter()
-            .filter(|c| *c.get_contained() == FilterState::Positive)
+            .filter(|c| *c.state() == FilterState::Positive)
             .map(|c| match c.get_value(self) {
                 Some(u) => u.to_string(),
                 None => "-".to_string(),
             })
             .collect();
-        let rv = if lvls.is_empty() {
+        if lvls.is_empty() {
             format!("{}", self.sla_level)
         } else if lvls.contains(&"/".to_string()) {
             format!("{}({})", self.sla_level, lvls.join("/"))
         } else {
             lvls.join("/")
-        };
+        }
+    }
+}
 
-        if res {
-            Some((self, rv))
-        } else {
-            None
+/// Unit of a timed [`SpellDuration`], ordered by length.
+#[derive(
+    Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq, PartialOrd, Ord,
+)]
+pub enum TimeUnit {
+    Round,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeUnit {
+    pub fn get_all() -> [Self; 7] {
+        [
+            Self::Round,
+            Self::Minute,
+            Self::Hour,
+            Self::Day,
+            Self::Week,
+            Self::Month,
+            Self::Year,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Round => "round",
+            Self::Minute => "min",
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+
+    /// Parses a unit like "rounds", "min." or "hour".
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim_end_matches('.').to_lowercase();
+        let text = text.strip_suffix('s').unwrap_or(&text);
+        Some(match text {
+            "round" | "rd" => Self::Round,
+            "min" | "minute" => Self::Minute,
+            "hour" | "hr" | "h" => Self::Hour,
+            "day" | "d" => Self::Day,
+            "week" => Self::Week,
+            "month" => Self::Month,
+            "year" => Self::Year,
+            _ => return None,
+        })
+    }
+
+    pub fn rounds(&self) -> u64 {
+        match self {
+            Self::Round => 1,
+            Self::Minute => 10,
+            Self::Hour => 600,
+            Self::Day => 14_400,
+            Self::Week => 100_800,
+            Self::Month => 432_000,
+            Self::Year => 5_256_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub enum DurationKind {
+    Instantaneous,
+    Permanent,
+    /// Lasts as long as the caster concentrates, without a maximum.
+    Concentration,
+    /// `amount` units, times the caster level if `per_level`.
+    Timed {
+        amount: u32,
+        unit: TimeUnit,
+        per_level: bool,
+    },
+    /// Dice, "see text", "until discharged" and other durations without a fixed length.
+    Special,
+}
+
+/// The typed form of a duration like "concentration, up to 1 min./level (D)".
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub struct SpellDuration {
+    pub kind: DurationKind,
+    /// Needs concentration, if `kind` is timed at most that long.
+    pub concentration: bool,
+    pub dismissible: bool,
+    /// Ends when the spell is discharged, e.g. "1 min./level or until discharged".
+    pub discharge: bool,
+}
+
+impl Default for SpellDuration {
+    fn default() -> Self {
+        Self {
+            kind: DurationKind::Special,
+            concentration: false,
+            dismissible: false,
+            discharge: false,
+        }
+    }
+}
+
+impl SpellDuration {
+    /// Length in rounds at a caster level, infinite for permanent spells and spells that
+    /// wait until discharged, `None` if the length depends on the situation.
+    pub fn rounds(&self, caster_level: u32) -> Option<f64> {
+        match self.kind {
+            DurationKind::Instantaneous => Some(0.0),
+            DurationKind::Permanent => Some(f64::INFINITY),
+            DurationKind::Timed {
+                amount,
+                unit,
+                per_level,
+            } => {
+                let levels = if per_level { caster_level.max(1) } else { 1 };
+                Some((amount as u64 * unit.rounds() * levels as u64) as f64)
+            }
+            DurationKind::Special if self.discharge => Some(f64::INFINITY),
+            DurationKind::Concentration | DurationKind::Special => None,
+        }
+    }
+}
+
+/// The typed form of a range like "medium (100 ft. + 10 ft./level)".
+#[derive(Debug, Default, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub enum RangeValue {
+    Personal,
+    Touch,
+    /// 25 ft. + 5 ft./2 levels
+    Close,
+    /// 100 ft. + 10 ft./level
+    Medium,
+    /// 400 ft. + 40 ft./level
+    Long,
+    /// `feet`, times the caster level if `per_level`.
+    Fixed {
+        feet: u32,
+        per_level: bool,
+    },
+    Unlimited,
+    /// "see text", other planes and other ranges without a distance.
+    #[default]
+    Special,
+}
+
+impl RangeValue {
+    /// The range in feet at a caster level, `None` if it isn't a distance.
+    pub fn feet(&self, caster_level: u32) -> Option<f64> {
+        let level = caster_level.max(1) as f64;
+        Some(match self {
+            Self::Personal | Self::Touch => 0.0,
+            Self::Close => 25.0 + 5.0 * (level / 2.0).floor(),
+            Self::Medium => 100.0 + 10.0 * level,
+            Self::Long => 400.0 + 40.0 * level,
+            Self::Fixed { feet, per_level } => *feet as f64 * if *per_level { level } else { 1.0 },
+            Self::Unlimited => f64::INFINITY,
+            Self::Special => return None,
+        })
+    }
+
+    /// The range at a caster level for display, e.g. "150 ft." or "touch".
+    pub fn format(&self, caster_level: u32) -> Option<String> {
+        match self {
+            Self::Personal => Some("personal".to_string()),
+            Self::Touch => Some("touch".to_string()),
+            Self::Unlimited => Some("unlimited".to_string()),
+            _ => {
+                let feet = self.feet(caster_level)? as u64;
+                Some(match feet {
+                    5280 => "1 mile".to_string(),
+                    feet if feet > 5280 && feet % 5280 == 0 => format!("{} miles", feet / 5280),
+                    feet => format!("{feet} ft."),
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub enum Shape {
+    Burst,
+    Emanation,
+    Spread,
+    Cone,
+    Line,
+    Cylinder,
+    Wall,
+    Cube,
+    Sphere,
+}
+
+/// The geometry of an area or effect like "20-ft.-radius spread".
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub struct SpellShape {
+    pub shape: Shape,
+    /// Radius of round shapes, length of cones, lines and walls, edge of cubes.
+    pub feet: Option<u32>,
+    /// The size or the number of shapes grows with the caster level.
+    pub per_level: bool,
+}
+
+/// How many targets a spell has.
+#[derive(Debug, Default, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub enum TargetCount {
+    /// The spell has an area or effect instead.
+    #[default]
+    None,
+    You,
+    One,
+    /// A fixed number above one, a number per level or any number of targets.
+    Multiple,
+    /// "see text" and other targets that don't fit the above.
+    Special,
+}
+
+/// The typed form of a casting time like "1 standard action" or "10 minutes".
+#[derive(Debug, Default, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+pub enum CastingTime {
+    Swift,
+    Immediate,
+    Move,
+    Standard,
+    FullRound,
+    Timed {
+        amount: u32,
+        unit: TimeUnit,
+    },
+    /// "see text" and other times that don't fit the above.
+    #[default]
+    Special,
+}
+
+impl CastingTime {
+    /// Length of the casting time in rounds, actions are fractions of a round.
+    pub fn rounds(&self) -> Option<f64> {
+        match self {
+            Self::Swift | Self::Immediate => Some(0.1),
+            Self::Move => Some(0.25),
+            Self::Standard => Some(0.5),
+            Self::FullRound => Some(1.0),
+            Self::Timed { amount, unit } => Some((*amount as u64 * unit.rounds()) as f64),
+            Self::Special => None,
+        }
+    }
+}
+
+/// A spell list, the classes, the domains, bloodlines, patrons and mysteries granting spells
+/// and the occultist implements.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, Eq, PartialEq, Hash)]
+pub enum Class {
+    Sorcerer,
+    Wizard,
+    Cleric,
+    Druid,
+    Ranger,
+    Bard,
+    Paladin,
+    Alchemist,
+    Summoner,
+    Witch,
+    Inquisitor,
+    Oracle,
+    Antipaladin,
+    Magus,
+    Adept,
+    Bloodrager,
+    Shaman,
+    Psychic,
+    Medium,
+    Mesmerist,
+    Occultist,
+    Spiritualist,
+    Skald,
+    Investigator,
+    Hunter,
+    UncSummoner,
+    Arcanist,
+    Warpriest,
+    Domain(String),
+    Bloodline(String),
+    Patron(String),
+    Mystery(String),
+    /// The occultist spells of a school, occultists learn them with an implement of it.
+    Implement(String),
+    /// Lists without a class of their own here, e.g. "red mantis assassin".
+    Other(String),
+}
+
+impl Class {
+    /// Reads a class name as written in `spell_level`, like "wizard", "summoner (unchained)"
+    /// or "flame mystery".
+    pub fn parse(name: &str) -> Self {
+        let name = name.trim();
+        let lower = name.to_lowercase();
+        let granted = [
+            (" domain", Self::Domain as fn(String) -> Self),
+            (" bloodline", Self::Bloodline),
+            (" patron", Self::Patron),
+            (" mystery", Self::Mystery),
+            (" implement", Self::Implement),
+        ];
+        for (suffix, list) in granted {
+            if lower.ends_with(suffix) {
+                return list(name[..name.len() - suffix.len()].trim().to_string());
+            }
+        }
+        match lower.as_str() {
+            "sorcerer" => Self::Sorcerer,
+            "wizard" => Self::Wizard,
+            "cleric" => Self::Cleric,
+            "druid" => Self::Druid,
+            "ranger" => Self::Ranger,
+            "bard" => Self::Bard,
+            "paladin" => Self::Paladin,
+            "alchemist" => Self::Alchemist,
+            "summoner" => Self::Summoner,
+            "witch" => Self::Witch,
+            "inquisitor" => Self::Inquisitor,
+            "oracle" => Self::Oracle,
+            "antipaladin" => Self::Antipaladin,
+            "magus" => Self::Magus,
+            "adept" => Self::Adept,
+            "bloodrager" => Self::Bloodrager,
+            "shaman" => Self::Shaman,
+            "psychic" => Self::Psychic,
+            "medium" => Self::Medium,
+            "mesmerist" => Self::Mesmerist,
+            "occultist" => Self::Occultist,
+            "spiritualist" => Self::Spiritualist,
+            "skald" => Self::Skald,
+            "investigator" => Self::Investigator,
+            "hunter" => Self::Hunter,
+            "summoner (unchained)" | "unchained summoner" => Self::UncSummoner,
+            "arcanist" => Self::Arcanist,
+            "warpriest" => Self::Warpriest,
+            _ => Self::Other(name.to_string()),
+        }
+    }
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::UncSummoner => write!(f, "Unchained Summoner"),
+            Self::Domain(name) => write!(f, "{name} Domain"),
+            Self::Bloodline(name) => write!(f, "{name} Bloodline"),
+            Self::Patron(name) => write!(f, "{name} Patron"),
+            Self::Mystery(name) => write!(f, "{name} Mystery"),
+            Self::Implement(name) => write!(f, "{name} Implement"),
+            Self::Other(name) => write!(f, "{name}"),
+            class => write!(f, "{class:?}"),
         }
     }
 }
@@ -177,269 +535,51 @@ pub enum ClassType {
     Skald(FilterState),
     Investigator(FilterState),
     Hunter(FilterState),
+    #[name = "Unchained Summoner"]
     UncSummoner(FilterState),
+    Arcanist(FilterState),
+    Warpriest(FilterState),
 }
 
 impl ClassType {
     pub fn title(&self) -> String {
-        match self {
-            Self::Sorcerer(_) => "Sorcerer",
-            Self::Wizard(_) => "Wizard",
-            Self::Cleric(_) => "Cleric",
-            Self::Druid(_) => "Druid",
-            Self::Ranger(_) => "Ranger",
-            Self::Bard(_) => "Bard",
-            Self::Paladin(_) => "Paladin",
-            Self::Alchemist(_) => "Alchemist",
-            Self::Summoner(_) => "Summoner",
-            Self::Witch(_) => "Witch",
-            Self::Inquisitor(_) => "Inquisitor",
-            Self::Oracle(_) => "Oracle",
-            Self::Antipaladin(_) => "Antipaladin",
-            Self::Magus(_) => "Magus",
-            Self::Adept(_) => "Adept",
-            Self::Bloodrager(_) => "Bloodrager",
-            Self::Shaman(_) => "Shaman",
-            Self::Psychic(_) => "Psychic",
-            Self::Medium(_) => "Medium",
-            Self::Mesmerist(_) => "Mesmerist",
-            Self::Occultist(_) => "Occultist",
-            Self::Spiritualist(_) => "Spiritualist",
-            Self::Skald(_) => "Skald",
-            Self::Investigator(_) => "Investigator",
-            Self::Hunter(_) => "Hunter",
-            Self::UncSummoner(_) => "Unchained Summoner",
-        }
-        .to_string()
-    }
-
-    pub fn get_value(&self, spell: &Spell) -> Option<u32> {
-        match self {
-            Self::Sorcerer(FilterState::Positive) => spell.sor,
-            Self::Wizard(FilterState::Positive) => spell.wiz,
-            Self::Cleric(FilterState::Positive) => spell.cleric,
-            Self::Druid(FilterState::Positive) => spell.druid,
-            Self::Ranger(FilterState::Positive) => spell.ranger,
-            Self::Bard(FilterState::Positive) => spell.bard,
-            Self::Paladin(FilterState::Positive) => spell.paladin,
-            Self::Alchemist(FilterState::Positive) => spell.alchemist,
-            Self::Summoner(FilterState::Positive) => spell.summoner,
-            Self::Witch(FilterState::Positive) => spell.witch,
-            Self::Inquisitor(FilterState::Positive) => spell.inquisitor,
-            Self::Oracle(FilterState::Positive) => spell.oracle,
-            Self::Antipaladin(FilterState::Positive) => spell.antipaladin,
-            Self::Magus(FilterState::Positive) => spell.magus,
-            Self::Adept(FilterState::Positive) => spell.adept,
-            Self::Bloodrager(FilterState::Positive) => spell.bloodrager,
-            Self::Shaman(FilterState::Positive) => spell.shaman,
-            Self::Psychic(FilterState::Positive) => spell.psychic,
-            Self::Medium(FilterState::Positive) => spell.medium,
-            Self::Mesmerist(FilterState::Positive) => spell.mesmerist,
-            Self::Occultist(FilterState::Positive) => spell.occultist,
-            Self::Spiritualist(FilterState::Positive) => spell.spiritualist,
-            Self::Skald(FilterState::Positive) => spell.skald,
-            Self::Investigator(FilterState::Positive) => spell.investigator,
-            Self::Hunter(FilterState::Positive) => spell.hunter,
-            Self::UncSummoner(FilterState::Positive) => spell.summoner_unchained,
-            _ => None,
-        }
-    }
-
-    pub fn get_all() -> Vec<Self> {
-        [
-            Self::Sorcerer(FilterState::None),
-            Self::Wizard(FilterState::None),
-            Self::Cleric(FilterState::None),
-            Self::Druid(FilterState::None),
-            Self::Ranger(FilterState::None),
-            Self::Bard(FilterState::None),
-            Self::Paladin(FilterState::None),
-            Self::Alchemist(FilterState::None),
-            Self::Summoner(FilterState::None),
-            Self::Witch(FilterState::None),
-            Self::Inquisitor(FilterState::None),
-            Self::Oracle(FilterState::None),
-            Self::Antipaladin(FilterState::None),
-            Self::Magus(FilterState::None),
-            Self::Adept(FilterState::None),
-            Self::Bloodrager(FilterState::None),
-            Self::Shaman(FilterState::None),
-            Self::Psychic(FilterState::None),
-            Self::Medium(FilterState::None),
-            Self::Mesmerist(FilterState::None),
-            Self::Occultist(FilterState::None),
-            Self::Spiritualist(FilterState::None),
-            Self::Skald(FilterState::None),
-            Self::Investigator(FilterState::None),
-            Self::Hunter(FilterState::None),
-            Self::UncSummoner(FilterState::None),
-        ]
-        .into()
-    }
-
-    pub fn get_contained(&self) -> &FilterState {
-        match self {
-            ClassType::Sorcerer(filter_state) => filter_state,
-            ClassType::Wizard(filter_state) => filter_state,
-            ClassType::Cleric(filter_state) => filter_state,
-            ClassType::Druid(filter_state) => filter_state,
-            ClassType::Ranger(filter_state) => filter_state,
-            ClassType::Bard(filter_state) => filter_state,
-            ClassType::Paladin(filter_state) => filter_state,
-            ClassType::Alchemist(filter_state) => filter_state,
-            ClassType::Summoner(filter_state) => filter_state,
-            ClassType::Witch(filter_state) => filter_state,
-            ClassType::Inquisitor(filter_state) => filter_state,
-            ClassType::Oracle(filter_state) => filter_state,
-            ClassType::Antipaladin(filter_state) => filter_state,
-            ClassType::Magus(filter_state) => filter_state,
-            ClassType::Adept(filter_state) => filter_state,
-            ClassType::Bloodrager(filter_state) => filter_state,
-            ClassType::Shaman(filter_state) => filter_state,
-            ClassType::Psychic(filter_state) => filter_state,
-            ClassType::Medium(filter_state) => filter_state,
-            ClassType::Mesmerist(filter_state) => filter_state,
-            ClassType::Occultist(filter_state) => filter_state,
-            ClassType::Spiritualist(filter_state) => filter_state,
-            ClassType::Skald(filter_state) => filter_state,
-            ClassType::Investigator(filter_state) => filter_state,
-            ClassType::Hunter(filter_state) => filter_state,
-            ClassType::UncSummoner(filter_state) => filter_state,
-        }
+        self.class().to_string()
     }
 
-    pub fn n(&self) -> Self {
-        match self {
-            ClassType::Sorcerer(filter_state) => ClassType::Sorcerer(filter_state.n()),
-            ClassType::Wizard(filter_state) => ClassType::Wizard(filter_state.n()),
-            ClassType::Cleric(filter_state) => ClassType::Cleric(filter_state.n()),
-            ClassType::Druid(filter_state) => ClassType::Druid(filter_state.n()),
-            ClassType::Ranger(filter_state) => ClassType::Ranger(filter_state.n()),
-            ClassType::Bard(filter_state) => ClassType::Bard(filter_state.n()),
-            ClassType::Paladin(filter_state) => ClassType::Paladin(filter_state.n()),
-            ClassType::Alchemist(filter_state) => ClassType::Alchemist(filter_state.n()),
-            ClassType::Summoner(filter_state) => ClassType::Summoner(filter_state.n()),
-            ClassType::Witch(filter_state) => ClassType::Witch(filter_state.n()),
-            ClassType::Inquisitor(filter_state) => ClassType::Inquisitor(filter_state.n()),
-            ClassType::Oracle(filter_state) => ClassType::Oracle(filter_state.n()),
-            ClassType::Antipaladin(filter_state) => ClassType::Antipaladin(filter_state.n()),
-            ClassType::Magus(filter_state) => ClassType::Magus(filter_state.n()),
-            ClassType::Adept(filter_state) => ClassType::Adept(filter_state.n()),
-            ClassType::Bloodrager(filter_state) => ClassType::Bloodrager(filter_state.n()),
-            ClassType::Shaman(filter_state) => ClassType::Shaman(filter_state.n()),
-            ClassType::Psychic(filter_state) => ClassType::Psychic(filter_state.n()),
-            ClassType::Medium(filter_state) => ClassType::Medium(filter_state.n()),
-            ClassType::Mesmerist(filter_state) => ClassType::Mesmerist(filter_state.n()),
-            ClassType::Occultist(filter_state) => ClassType::Occultist(filter_state.n()),
-            ClassType::Spiritualist(filter_state) => ClassType::Spiritualist(filter_state.n()),
-            ClassType::Skald(filter_state) => ClassType::Skald(filter_state.n()),
-            ClassType::Investigator(filter_state) => ClassType::Investigator(filter_state.n()),
-            ClassType::Hunter(filter_state) => ClassType::Hunter(filter_state.n()),
-            ClassType::UncSummoner(filter_state) => ClassType::UncSummoner(filter_state.n()),
-        }
+    /// The spell list the class casts from.
+    pub fn class(&self) -> Class {
+        // the names are the ones used in `spell_level`
+        Class::parse(self.name())
     }
 
-    pub fn p(&self) -> Self {
-        match self {
-            ClassType::Sorcerer(filter_state) => ClassType::Sorcerer(filter_state.p()),
-            ClassType::Wizard(filter_state) => ClassType::Wizard(filter_state.p()),
-            ClassType::Cleric(filter_state) => ClassType::Cleric(filter_state.p()),
-            ClassType::Druid(filter_state) => ClassType::Druid(filter_state.p()),
-            ClassType::Ranger(filter_state) => ClassType::Ranger(filter_state.p()),
-            ClassType::Bard(filter_state) => ClassType::Bard(filter_state.p()),
-            ClassType::Paladin(filter_state) => ClassType::Paladin(filter_state.p()),
-            ClassType::Alchemist(filter_state) => ClassType::Alchemist(filter_state.p()),
-            ClassType::Summoner(filter_state) => ClassType::Summoner(filter_state.p()),
-            ClassType::Witch(filter_state) => ClassType::Witch(filter_state.p()),
-            ClassType::Inquisitor(filter_state) => ClassType::Inquisitor(filter_state.p()),
-            ClassType::Oracle(filter_state) => ClassType::Oracle(filter_state.p()),
-            ClassType::Antipaladin(filter_state) => ClassType::Antipaladin(filter_state.p()),
-            ClassType::Magus(filter_state) => ClassType::Magus(filter_state.p()),
-            ClassType::Adept(filter_state) => ClassType::Adept(filter_state.p()),
-            ClassType::Bloodrager(filter_state) => ClassType::Bloodrager(filter_state.p()),
-            ClassType::Shaman(filter_state) => ClassType::Shaman(filter_state.p()),
-            ClassType::Psychic(filter_state) => ClassType::Psychic(filter_state.p()),
-            ClassType::Medium(filter_state) => ClassType::Medium(filter_state.p()),
-            ClassType::Mesmerist(filter_state) => ClassType::Mesmerist(filter_state.p()),
-            ClassType::Occultist(filter_state) => ClassType::Occultist(filter_state.p()),
-            ClassType::Spiritualist(filter_state) => ClassType::Spiritualist(filter_state.p()),
-            ClassType::Skald(filter_state) => ClassType::Skald(filter_state.p()),
-            ClassType::Investigator(filter_state) => ClassType::Investigator(filter_state.p()),
-            ClassType::Hunter(filter_state) => ClassType::Hunter(filter_state.p()),
-            ClassType::UncSummoner(filter_state) => ClassType::UncSummoner(filter_state.p()),
+    pub fn get_value(&self, spell: &Spell) -> Option<u32> {
+        match self.state() {
+            FilterState::Positive => spell.class_level(&self.class()),
+            _ => None,
         }
     }
 
     pub fn test_cls(&self, spell: &Spell) -> bool {
-        match self {
-            ClassType::Sorcerer(FilterState::Positive) => spell.sor.is_some(),
-            ClassType::Wizard(FilterState::Positive) => spell.wiz.is_some(),
-            ClassType::Cleric(FilterState::Positive) => spell.cleric.is_some(),
-            ClassType::Druid(FilterState::Positive) => spell.druid.is_some(),
-            ClassType::Ranger(FilterState::Positive) => spell.ranger.is_some(),
-            ClassType::Bard(FilterState::Positive) => spell.bard.is_some(),
-            ClassType::Paladin(FilterState::Positive) => spell.paladin.is_some(),
-            ClassType::Alchemist(FilterState::Positive) => spell.alchemist.is_some(),
-            ClassType::Summoner(FilterState::Positive) => spell.summoner.is_some(),
-            ClassType::Witch(FilterState::Positive) => spell.witch.is_some(),
-            ClassType::Inquisitor(FilterState::Positive) => spell.inquisitor.is_some(),
-            ClassType::Oracle(FilterState::Positive) => spell.oracle.is_some(),
-            ClassType::Antipaladin(FilterState::Positive) => spell.antipaladin.is_some(),
-            ClassType::Magus(FilterState::Positive) => spell.magus.is_some(),
-            ClassType::Adept(FilterState::Positive) => spell.adept.is_some(),
-            ClassType::Bloodrager(FilterState::Positive) => spell.bloodrager.is_some(),
-            ClassType::Shaman(FilterState::Positive) => spell.shaman.is_some(),
-            ClassType::Psychic(FilterState::Positive) => spell.psychic.is_some(),
-            ClassType::Medium(FilterState::Positive) => spell.medium.is_some(),
-            ClassType::Mesmerist(FilterState::Positive) => spell.mesmerist.is_some(),
-            ClassType::Occultist(FilterState::Positive) => spell.occultist.is_some(),
-            ClassType::Spiritualist(FilterState::Positive) => spell.spiritualist.is_some(),
-            ClassType::Skald(FilterState::Positive) => spell.skald.is_some(),
-            ClassType::Investigator(FilterState::Positive) => spell.investigator.is_some(),
-            ClassType::Hunter(FilterState::Positive) => spell.hunter.is_some(),
-            ClassType::UncSummoner(FilterState::Positive) => spell.summoner_unchained.is_some(),
-            ClassType::Sorcerer(FilterState::Negative) => spell.sor.is_none(),
-            ClassType::Wizard(FilterState::Negative) => spell.wiz.is_none(),
-            ClassType::Cleric(FilterState::Negative) => spell.cleric.is_none(),
-            ClassType::Druid(FilterState::Negative) => spell.druid.is_none(),
-            ClassType::Ranger(FilterState::Negative) => spell.ranger.is_none(),
-            ClassType::Bard(FilterState::Negative) => spell.bard.is_none(),
-            ClassType::Paladin(FilterState::Negative) => spell.paladin.is_none(),
-            ClassType::Alchemist(FilterState::Negative) => spell.alchemist.is_none(),
-            ClassType::Summoner(FilterState::Negative) => spell.summoner.is_none(),
-            ClassType::Witch(FilterState::Negative) => spell.witch.is_none(),
-            ClassType::Inquisitor(FilterState::Negative) => spell.inquisitor.is_none(),
-            ClassType::Oracle(FilterState::Negative) => spell.oracle.is_none(),
-            ClassType::Antipaladin(FilterState::Negative) => spell.antipaladin.is_none(),
-            ClassType::Magus(FilterState::Negative) => spell.magus.is_none(),
-            ClassType::Adept(FilterState::Negative) => spell.adept.is_none(),
-            ClassType::Bloodrager(FilterState::Negative) => spell.bloodrager.is_none(),
-            ClassType::Shaman(FilterState::Negative) => spell.shaman.is_none(),
-            ClassType::Psychic(FilterState::Negative) => spell.psychic.is_none(),
-            ClassType::Medium(FilterState::Negative) => spell.medium.is_none(),
-            ClassType::Mesmerist(FilterState::Negative) => spell.mesmerist.is_none(),
-            ClassType::Occultist(FilterState::Negative) => spell.occultist.is_none(),
-            ClassType::Spiritualist(FilterState::Negative) => spell.spiritualist.is_none(),
-            ClassType::Skald(FilterState::Negative) => spell.skald.is_none(),
-            ClassType::Investigator(FilterState::Negative) => spell.investigator.is_none(),
-            ClassType::Hunter(FilterState::Negative) => spell.hunter.is_none(),
-            ClassType::UncSummoner(FilterState::Negative) => spell.summoner_unchained.is_none(),
-            _ => false,
+        let level = spell.class_level(&self.class());
+        match self.state() {
+            FilterState::Positive => level.is_some(),
+            FilterState::Negative => level.is_none(),
+            FilterState::None => false,
         }
     }
 
     pub fn create_btn(&self, ui: &mut egui::Ui) -> Self {
         let btn = egui::Button::new(self.title());
         let resp = ui.add(if self.some_filter() {
-            btn.fill(self.get_contained().get_color())
+            btn.fill(self.state().get_color())
         } else {
             btn
         });
 
         if resp.clicked() {
-            self.n()
+            self.with_state(self.state().n())
         } else if resp.secondary_clicked() {
-            self.p()
+            self.with_state(self.state().p())
         } else {
             self.clone()
         }
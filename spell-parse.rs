@@ -0,0 +1,210 @@
+// Parsers for the typed spell fields, used by the build script and tested in src/spell.rs.
+
+/// Splits "Fire (4), Sun (5)" into names and levels.
+fn granted(text: &str) -> Vec<(String, u32)> {
+    text.split(',')
+        .filter_map(|entry| {
+            let (name, level) = entry.trim().strip_suffix(')')?.rsplit_once('(')?;
+            Some((name.trim().to_string(), level.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn parse_duration(text: &str, dismissible: bool) -> SpellDuration {
+    let text = text.trim().to_lowercase();
+    let concentration = text.starts_with("concentration");
+    let kind = if text.starts_with("instantaneous") {
+        DurationKind::Instantaneous
+    } else if text.starts_with("permanent") {
+        DurationKind::Permanent
+    } else if let Some((amount, unit, per_level)) = quantity(&text) {
+        match TimeUnit::parse(unit) {
+            Some(unit) => DurationKind::Timed {
+                amount,
+                unit,
+                per_level,
+            },
+            None => DurationKind::Special,
+        }
+    } else if concentration {
+        DurationKind::Concentration
+    } else {
+        DurationKind::Special
+    };
+    SpellDuration {
+        kind,
+        concentration,
+        dismissible: dismissible || text.contains("(d)"),
+        discharge: text.contains("discharge"),
+    }
+}
+
+fn parse_casting_time(text: &str) -> CastingTime {
+    const ACTIONS: [(&str, CastingTime); 6] = [
+        ("swift action", CastingTime::Swift),
+        ("immediate action", CastingTime::Immediate),
+        ("move action", CastingTime::Move),
+        ("standard action", CastingTime::Standard),
+        ("full-round action", CastingTime::FullRound),
+        ("full round action", CastingTime::FullRound),
+    ];
+    let text = text.trim().to_lowercase();
+    // "1 standard action or 1 round" is cast with the action mentioned first
+    let action = ACTIONS
+        .iter()
+        .filter_map(|(name, action)| text.find(name).map(|i| (i, *action)))
+        .min_by_key(|(i, _)| *i);
+    if let Some((_, action)) = action {
+        return action;
+    }
+    match quantity(&text).and_then(|(amount, unit, _)| Some((amount, TimeUnit::parse(unit)?))) {
+        Some((amount, unit)) => CastingTime::Timed { amount, unit },
+        None => CastingTime::Special,
+    }
+}
+
+/// Whether the comma separated `components` contain one starting with `name`, ignoring
+/// the descriptions of materials and foci in parentheses.
+fn has_component(components: &str, name: &str) -> bool {
+    let mut depth = 0;
+    let mut outer = String::new();
+    for c in components.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if depth == 0 => outer.push(c),
+            _ => {}
+        }
+    }
+    outer
+        .to_lowercase()
+        .split(',')
+        .any(|component| component.trim().starts_with(name))
+}
+
+fn parse_range(text: &str) -> RangeValue {
+    // some ranges only give the formula of close, medium or long range
+    const FORMULAS: [(&str, RangeValue); 3] = [
+        ("25 ft. + 5 ft./2 levels", RangeValue::Close),
+        ("100 ft. + 10 ft./level", RangeValue::Medium),
+        ("400 ft. + 40 ft./level", RangeValue::Long),
+    ];
+    let text = text.trim().to_lowercase();
+    let formula = FORMULAS
+        .iter()
+        .find(|(formula, _)| text.contains(formula))
+        .map(|(_, range)| *range);
+    if let Some(range) = formula {
+        range
+    } else if text.starts_with("personal") || text.starts_with("you") {
+        RangeValue::Personal
+    } else if text.starts_with("touch") {
+        RangeValue::Touch
+    } else if text.starts_with("close") {
+        RangeValue::Close
+    } else if text.starts_with("medium") {
+        RangeValue::Medium
+    } else if text.starts_with("long") {
+        RangeValue::Long
+    } else if text.starts_with("unlimited") || text.starts_with("anywhere") {
+        RangeValue::Unlimited
+    } else {
+        match quantity(&text) {
+            Some((feet, "ft" | "feet" | "foot", per_level)) => {
+                RangeValue::Fixed { feet, per_level }
+            }
+            Some((miles, "mile" | "miles", per_level)) => RangeValue::Fixed {
+                feet: miles * 5280,
+                per_level,
+            },
+            _ => RangeValue::Special,
+        }
+    }
+}
+
+fn parse_shape(text: &str) -> Option<SpellShape> {
+    // "cone-shaped burst" is a cone and "cylinder (10-ft. radius ...)" a cylinder,
+    // so the more specific shapes are looked for first
+    const SHAPES: [(&str, Shape); 9] = [
+        ("cone", Shape::Cone),
+        ("line", Shape::Line),
+        ("cylinder", Shape::Cylinder),
+        ("wall", Shape::Wall),
+        ("cube", Shape::Cube),
+        ("sphere", Shape::Sphere),
+        ("emanation", Shape::Emanation),
+        ("spread", Shape::Spread),
+        ("burst", Shape::Burst),
+    ];
+    let text = text.to_lowercase();
+    // whole words, also in plural, so "outline" isn't a line
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|word| word.strip_suffix('s').unwrap_or(word))
+        .collect();
+    let shape = SHAPES
+        .iter()
+        .find(|(name, _)| words.contains(name))
+        .map(|(_, shape)| *shape)?;
+    // "20-ft.-radius" has the same amount and unit as "20 ft. radius"
+    let feet = match quantity(&text.replace(['-', '('], " ")) {
+        Some((feet, "ft" | "feet" | "foot", _)) => Some(feet),
+        _ => None,
+    };
+    Some(SpellShape {
+        shape,
+        feet,
+        per_level: text.contains("/level"),
+    })
+}
+
+fn parse_targets(text: &str) -> TargetCount {
+    const NUMBERS: [&str; 9] = [
+        "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    ];
+    let text = text.trim().to_lowercase();
+    let first = text
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default();
+    if text.is_empty() {
+        TargetCount::None
+    } else if first == "you" {
+        TargetCount::You
+    } else if text.contains("/level")
+        || text.contains("or more")
+        || text.contains("any number")
+        || text.starts_with("up to")
+        || text.starts_with("all ")
+        || NUMBERS.contains(&first)
+        || first.parse::<u32>().is_ok_and(|n| n > 1)
+    {
+        TargetCount::Multiple
+    } else if matches!(first, "one" | "a" | "an" | "1" | "creature" | "object") {
+        TargetCount::One
+    } else {
+        TargetCount::Special
+    }
+}
+
+/// The first amount with a unit in the text, e.g. "10 min./level" is `(10, "min", true)`.
+fn quantity(text: &str) -> Option<(u32, &str, bool)> {
+    let mut words = text.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        let Ok(amount) = word.replace(',', "").parse::<u32>() else {
+            continue;
+        };
+        let Some(unit) = words.peek() else {
+            break;
+        };
+        let per_level = unit.contains("/level");
+        let unit = unit
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .next()
+            .unwrap_or_default();
+        if !unit.is_empty() {
+            return Some((amount, unit, per_level));
+        }
+    }
+    None
+}
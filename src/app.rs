@@ -1,20 +1,32 @@
+use std::{borrow::Cow, cmp::Reverse};
+
 use egui::{Id, RichText};
 use egui_extras::{Column, TableBuilder, TableRow};
-use filter_repr::FilterRepr;
-
-use regex::Regex;
+use filter_repr::{FilterRepr, FilterState};
 use serde::{Deserialize, Deserializer};
 
 use crate::{
+    cards::{self, CardSize},
+    export::{self, ExportFormat},
     filter_row,
     filters::{
         Domain, Level, Save, SpellComponent, SpellDescriptor, SpellRange, SpellResistance,
         SpellSource, Spellschool, Subschool,
     },
-    spell::{ClassType, Spell, SpellMeta, BONUS_INFO},
-    util::{html2egui, toggle},
+    query::{LevelRange, MinArea, MinDuration, MinRange, SpellQuery},
+    query_parser::QueryError,
+    sorting,
+    spell::{ClassType, Spell, SpellMeta, ALL_SPELLS, BONUS_INFO},
+    spellbook::{material_cost_summary, SpellbookWindow},
+    util::{
+        body_format, highlight, html2egui, min_area_ui, min_duration_ui, min_range_ui, toggle,
+        url_encode,
+    },
 };
 
+/// Where the web build is hosted, used for share links.
+const SHARE_URL: &str = "https://froith.github.io/pf1_spell_search/";
+
 #[cfg(target_arch = "wasm32")]
 pub static UPDATE_AVAILABLE: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
 
@@ -26,7 +38,7 @@ where
     Ok(s.split(",").map(|x| x.to_string()).collect())
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
 pub enum RowOrder {
     None,
     Ascending,
@@ -50,6 +62,8 @@ impl RowOrder {
         }
     }
 
+    /// Compares two rows by a column, `c` and `d` are their level strings and `query` the
+    /// query they were found with.
     fn compare(
         &self,
         col: &ColType,
@@ -57,49 +71,66 @@ impl RowOrder {
         b: &Spell,
         c: &String,
         d: &String,
+        query: &SpellQuery,
     ) -> std::cmp::Ordering {
-        match (self, col) {
-            (Self::Ascending, ColType::Name(_)) => a.name.cmp(&b.name),
-            (Self::Ascending, ColType::School(_)) => a.school.cmp(&b.school),
-            (Self::Ascending, ColType::Level(_)) => c.cmp(d),
-            (Self::Ascending, ColType::Subschools(_)) => a.subschool.cmp(&b.subschool),
-            (Self::Ascending, ColType::Domain(_)) => a.domain.cmp(&b.domain),
-            (Self::Ascending, ColType::Descriptors(_)) => a.descriptors.cmp(&b.descriptors),
-            (Self::Ascending, ColType::Components(_)) => a.components.cmp(&b.components),
-            (Self::Ascending, ColType::Range(_)) => a.range.cmp(&b.range),
-            (Self::Ascending, ColType::Area(_)) => a.area.cmp(&b.area),
-            (Self::Ascending, ColType::Effect(_)) => a.effect.cmp(&b.effect),
-            (Self::Ascending, ColType::Targets(_)) => a.targets.cmp(&b.targets),
-            (Self::Ascending, ColType::Duration(_)) => a.duration.cmp(&b.duration),
-            (Self::Ascending, ColType::SavingThrow(_)) => a.saving_throw.cmp(&b.duration),
-            (Self::Ascending, ColType::SpellResistance(_)) => {
-                a.spell_resistance.cmp(&b.spell_resistance)
+        let classes = &query.selected_classes;
+        let ordering = match col {
+            ColType::None => std::cmp::Ordering::Equal,
+            ColType::Name(_) => a.name.cmp(&b.name),
+            ColType::School(_) => a.school.cmp(&b.school),
+            ColType::Level(_) => {
+                sorting::cmp_known(sorting::level(a, classes), sorting::level(b, classes))
+                    .then_with(|| c.cmp(d))
+            }
+            ColType::Subschools(_) => a.subschool.cmp(&b.subschool),
+            ColType::Domain(_) => a.domain.cmp(&b.domain),
+            ColType::Descriptors(_) => a.descriptors.cmp(&b.descriptors),
+            ColType::Components(_) => a.components.cmp(&b.components),
+            ColType::MaterialCost(_) => sorting::cmp_known(a.material_cost(), b.material_cost()),
+            ColType::CastingTime(_) => {
+                sorting::cmp_known(sorting::casting_time(a), sorting::casting_time(b))
+                    .then_with(|| a.casting_time.cmp(&b.casting_time))
             }
-            (Self::Ascending, ColType::Description(_)) => a.description.cmp(&b.description),
-            (Self::Ascending, ColType::Source(_)) => a.source.cmp(&b.source),
-            (Self::Descending, ColType::Name(_)) => b.name.cmp(&a.name),
-            (Self::Descending, ColType::School(_)) => b.school.cmp(&a.school),
-            (Self::Descending, ColType::Level(_)) => d.cmp(c),
-            (Self::Descending, ColType::Subschools(_)) => b.subschool.cmp(&a.subschool),
-            (Self::Descending, ColType::Domain(_)) => b.domain.cmp(&a.domain),
-            (Self::Descending, ColType::Descriptors(_)) => b.descriptors.cmp(&a.descriptors),
-            (Self::Descending, ColType::Components(_)) => b.components.cmp(&a.components),
-            (Self::Descending, ColType::Range(_)) => b.range.cmp(&a.range),
-            (Self::Descending, ColType::Area(_)) => b.area.cmp(&a.area),
-            (Self::Descending, ColType::Effect(_)) => b.effect.cmp(&a.effect),
-            (Self::Descending, ColType::Targets(_)) => b.targets.cmp(&a.targets),
-            (Self::Descending, ColType::Duration(_)) => b.duration.cmp(&a.duration),
-            (Self::Descending, ColType::SavingThrow(_)) => b.saving_throw.cmp(&a.saving_throw),
-            (Self::Descending, ColType::SpellResistance(_)) => {
-                b.spell_resistance.cmp(&a.spell_resistance)
+            ColType::Range(_) | ColType::RangeAtLevel(_) => {
+                sorting::cmp_known(sorting::range(a), sorting::range(b))
+                    .then_with(|| a.range.cmp(&b.range))
             }
-            (Self::Descending, ColType::Description(_)) => b.description.cmp(&a.description),
-            (Self::Descending, ColType::Source(_)) => b.source.cmp(&a.source),
-            (_, _) => std::cmp::Ordering::Equal,
+            ColType::Area(_) => a.area.cmp(&b.area),
+            ColType::Effect(_) => a.effect.cmp(&b.effect),
+            ColType::Targets(_) => a.targets.cmp(&b.targets),
+            ColType::Duration(_) => sorting::cmp_known(sorting::duration(a), sorting::duration(b))
+                .then_with(|| a.duration.cmp(&b.duration)),
+            ColType::SavingThrow(_) => a.saving_throw.cmp(&b.saving_throw),
+            ColType::SpellResistance(_) => a.spell_resistance.cmp(&b.spell_resistance),
+            ColType::Dismissible(_) => a.dismissible.cmp(&b.dismissible),
+            ColType::Shapeable(_) => a.shapeable.cmp(&b.shapeable),
+            ColType::Mythic(_) => a.mythic.cmp(&b.mythic),
+            ColType::Augmented(_) => (!a.augmented.is_empty()).cmp(&!b.augmented.is_empty()),
+            ColType::Description(_) => a.description.cmp(&b.description),
+            // ascending puts the best matches first, like a search engine, and spells without
+            // any of the words last
+            ColType::Relevance(_) => sorting::cmp_known(
+                query.relevance(a).map(Reverse),
+                query.relevance(b).map(Reverse),
+            ),
+            ColType::Source(_) => a.source.cmp(&b.source),
+        };
+        match self {
+            Self::None => std::cmp::Ordering::Equal,
+            Self::Ascending => ordering,
+            Self::Descending => ordering.reverse(),
         }
     }
 }
 
+/// One key of the table sort, earlier keys take priority and later ones break ties.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
+struct SortKey {
+    column: ColType,
+    /// Never `RowOrder::None`, unsorted columns have no key.
+    order: RowOrder,
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, Eq, PartialEq)]
 pub enum ColType {
     None,
@@ -110,14 +141,22 @@ pub enum ColType {
     Domain(bool),
     Descriptors(bool),
     Components(bool),
+    MaterialCost(bool),
+    CastingTime(bool),
     Range(bool),
+    RangeAtLevel(bool),
     Area(bool),
     Effect(bool),
     Targets(bool),
     Duration(bool),
     SavingThrow(bool),
     SpellResistance(bool),
+    Dismissible(bool),
+    Shapeable(bool),
+    Mythic(bool),
+    Augmented(bool),
     Description(bool),
+    Relevance(bool),
     Source(bool),
 }
 
@@ -132,41 +171,147 @@ impl ColType {
             Self::Domain(_) => "Domain",
             Self::Descriptors(_) => "Descriptor",
             Self::Components(_) => "Components",
+            Self::MaterialCost(_) => "Material Cost",
+            Self::CastingTime(_) => "Casting Time",
             Self::Range(_) => "Range",
+            Self::RangeAtLevel(_) => "Range at CL",
             Self::Area(_) => "Area",
             Self::Effect(_) => "Effect",
             Self::Targets(_) => "Targets",
             Self::Duration(_) => "Duration",
             Self::SavingThrow(_) => "Saving Throw",
             Self::SpellResistance(_) => "Spell Resistance",
+            Self::Dismissible(_) => "Dismissible",
+            Self::Shapeable(_) => "Shapeable",
+            Self::Mythic(_) => "Mythic",
+            Self::Augmented(_) => "Augmented",
             Self::Description(_) => "Description",
+            Self::Relevance(_) => "Relevance",
             Self::Source(_) => "Source",
         }
         .to_string()
     }
 
-    fn get_all() -> Vec<(Self, RowOrder)> {
+    /// The text of a cell of this column, `level` is the level string of the row,
+    /// `caster_level` the one computed values are shown for and `query` the one the row was
+    /// found with.
+    fn value<'a>(
+        &self,
+        spell: &'a Spell,
+        level: &'a str,
+        caster_level: u32,
+        query: &SpellQuery,
+    ) -> Cow<'a, str> {
+        Cow::Borrowed(match self {
+            Self::None => "",
+            Self::Name(_) => &spell.name,
+            Self::School(_) => &spell.school,
+            Self::Level(_) => level,
+            Self::Subschools(_) => &spell.subschool,
+            Self::Domain(_) => &spell.domain,
+            Self::Descriptors(_) => &spell.descriptors,
+            Self::Components(_) => &spell.components,
+            Self::MaterialCost(_) => {
+                return match spell.material_cost() {
+                    Some(0) => Cow::Borrowed(""),
+                    Some(cost) => Cow::Owned(format!("{cost} gp")),
+                    None => Cow::Borrowed("unknown"),
+                }
+            }
+            Self::CastingTime(_) => &spell.casting_time,
+            Self::Range(_) => &spell.range,
+            Self::RangeAtLevel(_) => {
+                return spell
+                    .range_value
+                    .format(caster_level)
+                    .map_or(Cow::Borrowed(&spell.range), Cow::Owned)
+            }
+            Self::Area(_) => &spell.area,
+            Self::Effect(_) => &spell.effect,
+            Self::Targets(_) => &spell.targets,
+            Self::Duration(_) => &spell.duration,
+            Self::SavingThrow(_) => &spell.saving_throw,
+            Self::SpellResistance(_) => &spell.spell_resistance,
+            Self::Dismissible(_) => yes(spell.dismissible),
+            Self::Shapeable(_) => yes(spell.shapeable),
+            Self::Mythic(_) => yes(spell.mythic),
+            Self::Augmented(_) => yes(!spell.augmented.is_empty()),
+            Self::Description(_) => &spell.short_description,
+            Self::Relevance(_) => {
+                return query
+                    .relevance(spell)
+                    .map_or(Cow::Borrowed(""), |r| Cow::Owned(format!("{r:.2}")))
+            }
+            Self::Source(_) => &spell.source,
+        })
+    }
+
+    /// Short name used in share links.
+    fn key(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Name(_) => "name",
+            Self::School(_) => "school",
+            Self::Level(_) => "level",
+            Self::Subschools(_) => "subschool",
+            Self::Domain(_) => "domain",
+            Self::Descriptors(_) => "descriptor",
+            Self::Components(_) => "components",
+            Self::MaterialCost(_) => "cost",
+            Self::CastingTime(_) => "casting",
+            Self::Range(_) => "range",
+            Self::RangeAtLevel(_) => "range_cl",
+            Self::Area(_) => "area",
+            Self::Effect(_) => "effect",
+            Self::Targets(_) => "targets",
+            Self::Duration(_) => "duration",
+            Self::SavingThrow(_) => "save",
+            Self::SpellResistance(_) => "sr",
+            Self::Dismissible(_) => "dismissible",
+            Self::Shapeable(_) => "shapeable",
+            Self::Mythic(_) => "mythic",
+            Self::Augmented(_) => "augmented",
+            Self::Description(_) => "description",
+            Self::Relevance(_) => "relevance",
+            Self::Source(_) => "source",
+        }
+    }
+
+    fn get_all() -> Vec<Self> {
         [
-            (Self::Name(true), RowOrder::None),
-            (Self::School(true), RowOrder::None),
-            (Self::Level(true), RowOrder::None),
-            (Self::Subschools(false), RowOrder::None),
-            (Self::Domain(false), RowOrder::None),
-            (Self::Descriptors(false), RowOrder::None),
-            (Self::Components(false), RowOrder::None),
-            (Self::Range(false), RowOrder::None),
-            (Self::Area(false), RowOrder::None),
-            (Self::Effect(false), RowOrder::None),
-            (Self::Targets(false), RowOrder::None),
-            (Self::Duration(false), RowOrder::None),
-            (Self::SavingThrow(false), RowOrder::None),
-            (Self::SpellResistance(false), RowOrder::None),
-            (Self::Description(true), RowOrder::None),
-            (Self::Source(false), RowOrder::None),
+            Self::Name(true),
+            Self::School(true),
+            Self::Level(true),
+            Self::Subschools(false),
+            Self::Domain(false),
+            Self::Descriptors(false),
+            Self::Components(false),
+            Self::MaterialCost(false),
+            Self::CastingTime(false),
+            Self::Range(false),
+            Self::RangeAtLevel(false),
+            Self::Area(false),
+            Self::Effect(false),
+            Self::Targets(false),
+            Self::Duration(false),
+            Self::SavingThrow(false),
+            Self::SpellResistance(false),
+            Self::Dismissible(false),
+            Self::Shapeable(false),
+            Self::Mythic(false),
+            Self::Augmented(false),
+            Self::Description(true),
+            Self::Relevance(false),
+            Self::Source(false),
         ]
         .into()
     }
 
+    /// Whether both are the same column, regardless of visibility.
+    fn same(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
     fn get_bool(&self) -> bool {
         *match self {
             Self::None => &false,
@@ -177,14 +322,22 @@ impl ColType {
             Self::Domain(b) => b,
             Self::Descriptors(b) => b,
             Self::Components(b) => b,
+            Self::MaterialCost(b) => b,
+            Self::CastingTime(b) => b,
             Self::Range(b) => b,
+            Self::RangeAtLevel(b) => b,
             Self::Area(b) => b,
             Self::Effect(b) => b,
             Self::Targets(b) => b,
             Self::Duration(b) => b,
             Self::SavingThrow(b) => b,
             Self::SpellResistance(b) => b,
+            Self::Dismissible(b) => b,
+            Self::Shapeable(b) => b,
+            Self::Mythic(b) => b,
+            Self::Augmented(b) => b,
             Self::Description(b) => b,
+            Self::Relevance(b) => b,
             Self::Source(b) => b,
         }
     }
@@ -199,19 +352,54 @@ impl ColType {
             Self::Domain(ref mut b) => b,
             Self::Descriptors(ref mut b) => b,
             Self::Components(ref mut b) => b,
+            Self::MaterialCost(ref mut b) => b,
+            Self::CastingTime(ref mut b) => b,
             Self::Range(ref mut b) => b,
+            Self::RangeAtLevel(ref mut b) => b,
             Self::Area(ref mut b) => b,
             Self::Effect(ref mut b) => b,
             Self::Targets(ref mut b) => b,
             Self::Duration(ref mut b) => b,
             Self::SavingThrow(ref mut b) => b,
             Self::SpellResistance(ref mut b) => b,
+            Self::Dismissible(ref mut b) => b,
+            Self::Shapeable(ref mut b) => b,
+            Self::Mythic(ref mut b) => b,
+            Self::Augmented(ref mut b) => b,
             Self::Description(ref mut b) => b,
+            Self::Relevance(ref mut b) => b,
             Self::Source(ref mut b) => b,
         }
     }
 }
 
+/// Editor for the full text search, returns whether it was changed.
+fn search_ui(ui: &mut egui::Ui, query: &mut SpellQuery) -> bool {
+    let changed = ui
+        .add(
+            egui::text_edit::TextEdit::singleline(&mut query.search)
+                .hint_text("words, \"phrases\", prefix*"),
+        )
+        .on_hover_text(
+            "ranks the spells by relevance unless another sort is set, \
+            spells without any of the words come last but aren't hidden",
+        )
+        .changed();
+    if changed {
+        query.update_keywords();
+    }
+    changed
+}
+
+/// Cell text of the flag columns.
+fn yes(flag: bool) -> &'static str {
+    if flag {
+        "yes"
+    } else {
+        ""
+    }
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -221,6 +409,8 @@ pub struct SpellSearchApp {
     filter_window_active: bool,
     #[serde(skip)]
     source_window_active: bool,
+    #[serde(skip)]
+    spellbook_window_active: bool,
 }
 
 impl Default for SpellSearchApp {
@@ -229,6 +419,7 @@ impl Default for SpellSearchApp {
             spell_table: SpellTable::new(),
             filter_window_active: false,
             source_window_active: false,
+            spellbook_window_active: false,
         }
     }
 }
@@ -241,11 +432,23 @@ impl SpellSearchApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: Self = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        app.spell_table.migrate_filters();
+        app.spell_table.add_missing_columns();
+        app.spell_table.query.add_missing_filters();
+
+        // A share link overrides the stored search.
+        #[cfg(target_arch = "wasm32")]
+        if let Some(fragment) = cc.integration_info.web_info.location.hash.strip_prefix('#') {
+            if !fragment.is_empty() {
+                app.spell_table.apply_fragment(fragment);
+            }
         }
 
-        Default::default()
+        app
     }
 }
 
@@ -276,9 +479,11 @@ impl eframe::App for SpellSearchApp {
                 }
 
                 ui.menu_button("Columns", |ui| {
-                    for (col, _) in &mut self.spell_table.shown_columns {
+                    for col in &mut self.spell_table.shown_columns {
                         let title = col.title();
-                        ui.checkbox(col.get_bool_mut(), title);
+                        if ui.checkbox(col.get_bool_mut(), title).changed() && !col.get_bool() {
+                            self.spell_table.sort_keys.retain(|k| !k.column.same(col));
+                        }
                     }
                 });
 
@@ -290,21 +495,75 @@ impl eframe::App for SpellSearchApp {
                     self.source_window_active = !self.source_window_active;
                 };
 
+                if ui.button("Spellbooks").clicked() {
+                    self.spellbook_window_active = !self.spellbook_window_active;
+                };
+
+                ui.menu_button("Presets", |ui| {
+                    self.spell_table.presets_ui(ui);
+                });
+
+                ui.menu_button("Export", |ui| {
+                    for format in ExportFormat::get_all() {
+                        if ui.button(format.title()).clicked() {
+                            self.spell_table.export(format);
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.menu_button("Cards", |ui| {
+                    self.spell_table.cards_ui(ui);
+                });
+
+                if ui
+                    .button("Copy link")
+                    .on_hover_text("copy a link to the current search")
+                    .clicked()
+                {
+                    ctx.copy_text(format!("{SHARE_URL}#{}", self.spell_table.to_fragment()));
+                }
+
+                ui.add_space(16.0);
+                self.spell_table.query_bar_ui(ui);
+
                 if self.filter_window_active {
-                    self.spell_table
-                        .filter_window
-                        .filter_ui(ctx, &mut self.filter_window_active);
+                    self.spell_table.filter_window.filter_ui(
+                        ctx,
+                        &mut self.filter_window_active,
+                        &mut self.spell_table.query,
+                    );
                 }
 
                 if self.source_window_active {
-                    self.spell_table
-                        .source_window
-                        .filter_ui(ctx, &mut self.source_window_active);
+                    self.spell_table.source_window.filter_ui(
+                        ctx,
+                        &mut self.source_window_active,
+                        &mut self.spell_table.query,
+                        &mut self.spell_table.spellbook_window,
+                    );
+                }
+
+                if self.spellbook_window_active {
+                    if let Some(spell) = self
+                        .spell_table
+                        .spellbook_window
+                        .spellbook_ui(ctx, &mut self.spellbook_window_active)
+                    {
+                        self.spell_table.selected_spell = Some(spell.clone());
+                    }
                 }
 
                 let mut spell_to_add: Option<Spell> = None;
                 for (s, b, id) in &mut self.spell_table.selected_spell_windows {
-                    let r = self.spell_table.spell_window.spell_ui(ctx, b, s, *id);
+                    let r = self.spell_table.spell_window.spell_ui(
+                        ctx,
+                        b,
+                        s,
+                        *id,
+                        &mut self.spell_table.spellbook_window,
+                        &self.spell_table.query,
+                    );
                     match r {
                         Some((new_spell, true)) => {
                             spell_to_add = Some(new_spell);
@@ -364,14 +623,7 @@ impl eframe::App for SpellSearchApp {
         });
 
         if self.spell_table.shown_value.is_none() {
-            self.spell_table.filter_window.keywords = self
-                .spell_table
-                .filter_window
-                .description
-                .to_lowercase()
-                .split(",")
-                .map(|x| Regex::new(&format!("\\b{}\\b", x)))
-                .collect();
+            self.spell_table.query.update_keywords();
             self.spell_table.filter_window.filters_changed = true;
         }
 
@@ -379,25 +631,18 @@ impl eframe::App for SpellSearchApp {
 
         self.spell_table.table_ui(ctx);
 
-        if self.spell_table.filter_window.description
-            != self.spell_table.filter_window.prev_description
-        {
-            self.spell_table.filter_window.keywords = self
-                .spell_table
-                .filter_window
-                .description
-                .to_lowercase()
-                .split(",")
-                .map(|x| Regex::new(&format!("\\b{}\\b", x)))
-                .collect();
+        if self.spell_table.query.description != self.spell_table.filter_window.prev_description {
+            self.spell_table.query.update_keywords();
             self.spell_table.filter_window.filters_changed = true;
         }
         self.spell_table.filter_window.prev_description =
-            self.spell_table.filter_window.description.clone();
+            self.spell_table.query.description.clone();
 
         if self.spell_table.update_filters() {
             ctx.request_repaint();
         }
+        #[cfg(target_arch = "wasm32")]
+        self.spell_table.update_location();
         //if undo || redo {
         //    panic!("{:?}", (undo, redo))
         //}
@@ -409,33 +654,352 @@ struct SpellTable {
     #[serde(skip, default)]
     // This how you opt-out of serialization of a field
     shown_value: Option<Vec<(&'static Spell, String)>>,
+    /// Spell names close to the name filter when nothing was found.
+    #[serde(skip, default)]
+    suggestions: Vec<&'static str>,
 
-    shown_columns: Vec<(ColType, RowOrder)>,
+    #[serde(rename = "columns", default = "ColType::get_all")]
+    shown_columns: Vec<ColType>,
+    #[serde(default)]
+    sort_keys: Vec<SortKey>,
 
     filter_string: String,
     selected_spell: Option<Spell>,
     #[serde(skip, default)]
     selected_spell_windows: Vec<(Spell, bool, Id)>,
+    #[serde(default)]
+    query: SpellQuery,
+    #[serde(default)]
+    query_text: String,
+    #[serde(skip, default)]
+    query_error: Option<QueryError>,
+    #[serde(skip, default)]
+    query_text_focused: bool,
     filter_window: FilterWindow,
     source_window: SourceWindow,
     spell_window: SpellWindow,
     #[serde(skip, default)]
     counter: usize,
+    #[serde(default)]
+    presets: Vec<Preset>,
+    #[serde(default)]
+    spellbook_window: SpellbookWindow,
+    #[serde(default)]
+    card_size: CardSize,
+    /// Caster level of computed columns like "Range at CL".
+    #[serde(default = "default_caster_level")]
+    caster_level: u32,
+    #[serde(skip, default)]
+    preset_name: String,
+    #[serde(skip, default)]
+    renaming_preset: Option<(usize, String)>,
+    #[cfg(target_arch = "wasm32")]
+    #[serde(skip, default)]
+    location_fragment: String,
+}
+
+fn default_caster_level() -> u32 {
+    1
 }
 
 impl SpellTable {
+    /// Adds columns that are missing from a table saved by an older version, hidden.
+    fn add_missing_columns(&mut self) {
+        for mut col in ColType::get_all() {
+            if !self.shown_columns.iter().any(|c| c.same(&col)) {
+                *col.get_bool_mut() = false;
+                self.shown_columns.push(col);
+            }
+        }
+    }
+
+    /// Moves the filters of a state saved before [`SpellQuery`] existed into the query.
+    fn migrate_filters(&mut self) {
+        if let Some(query) = self.filter_window.legacy.take() {
+            self.query = query;
+        }
+        if let Some((source, source_or)) = self.source_window.legacy.take() {
+            self.query.source = source;
+            self.query.source_or = source_or;
+        }
+        // the stored copy may lack fields parsed by newer versions
+        if let Some(spell) = &self.selected_spell {
+            self.selected_spell = ALL_SPELLS.iter().find(|s| s.id == spell.id).cloned();
+        }
+    }
+
     fn new() -> Self {
-        let shown_columns: Vec<(ColType, RowOrder)> = ColType::get_all();
         Self {
             shown_value: None,
-            shown_columns,
+            suggestions: Vec::new(),
+            shown_columns: ColType::get_all(),
+            sort_keys: Vec::new(),
             filter_string: String::new(),
             selected_spell: None,
             selected_spell_windows: Vec::new(),
+            query: SpellQuery::new(),
+            query_text: String::new(),
+            query_error: None,
+            query_text_focused: false,
             filter_window: FilterWindow::new(),
             source_window: SourceWindow::new(),
             spell_window: SpellWindow::new(),
             counter: 0,
+            presets: Vec::new(),
+            spellbook_window: SpellbookWindow::new(),
+            card_size: CardSize::default(),
+            caster_level: default_caster_level(),
+            preset_name: String::new(),
+            renaming_preset: None,
+            #[cfg(target_arch = "wasm32")]
+            location_fragment: String::new(),
+        }
+    }
+
+    fn query_bar_ui(&mut self, ui: &mut egui::Ui) {
+        let error_span = self.query_error.as_ref().map(|e| e.span.clone());
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = error_layout_job(ui, text, error_span.clone());
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
+        };
+        let resp = ui.add(
+            egui::text_edit::TextEdit::singleline(&mut self.query_text)
+                .hint_text("class:wiz level:<=3 -descriptor:evil")
+                .desired_width(300.0)
+                .layouter(&mut layouter),
+        );
+        if resp.changed() {
+            match SpellQuery::parse(&self.query_text) {
+                Ok(mut query) => {
+                    query.update_keywords();
+                    self.query = query;
+                    self.query_error = None;
+                    self.filter_window.filters_changed = true;
+                }
+                Err(e) => {
+                    self.query_error = Some(e);
+                }
+            }
+        }
+        self.query_text_focused = resp.has_focus();
+
+        if let Some(e) = &self.query_error {
+            ui.label(egui::RichText::new("⚠").color(ui.visuals().error_fg_color))
+                .on_hover_text(e.message.as_str());
+        }
+    }
+
+    /// Encodes the query, visible columns and sort order for a share link,
+    /// e.g. `q=class:witch+level:2&cols=name,school,level&sort=-level`.
+    fn to_fragment(&self) -> String {
+        let cols: Vec<&str> = self
+            .shown_columns
+            .iter()
+            .filter(|col| col.get_bool())
+            .map(|col| col.key())
+            .collect();
+        let sort: Vec<String> = self
+            .sort_keys
+            .iter()
+            .map(|key| match key.order {
+                RowOrder::Descending => format!("-{}", key.column.key()),
+                _ => key.column.key().to_string(),
+            })
+            .collect();
+
+        let mut fragment = format!(
+            "q={}&cols={}",
+            url_encode(&self.query.to_query_string()),
+            cols.join(",")
+        );
+        if !sort.is_empty() {
+            fragment.push_str(&format!("&sort={}", sort.join(",")));
+        }
+        fragment
+    }
+
+    /// Restores the state encoded by [`SpellTable::to_fragment`], unknown parts are ignored.
+    #[cfg(target_arch = "wasm32")]
+    fn apply_fragment(&mut self, fragment: &str) {
+        for (key, value) in fragment.split('&').filter_map(|p| p.split_once('=')) {
+            let value = crate::util::url_decode(value);
+            match key {
+                "q" => match SpellQuery::parse(&value) {
+                    Ok(query) => {
+                        self.query = query;
+                        self.query.update_keywords();
+                        self.filter_window.filters_changed = true;
+                    }
+                    Err(e) => log::warn!("invalid query in link: {e}"),
+                },
+                "cols" => {
+                    let keys: Vec<&str> = value.split(',').collect();
+                    // listed columns first, in the listed order
+                    self.shown_columns.sort_by_key(|col| {
+                        keys.iter()
+                            .position(|k| *k == col.key())
+                            .unwrap_or(keys.len())
+                    });
+                    for col in &mut self.shown_columns {
+                        *col.get_bool_mut() = keys.contains(&col.key());
+                    }
+                }
+                "sort" => {
+                    // listed by priority, `-` marks a descending key
+                    self.sort_keys = value
+                        .split(',')
+                        .filter_map(|k| {
+                            let (key, order) = match k.strip_prefix('-') {
+                                Some(key) => (key, RowOrder::Descending),
+                                None => (k, RowOrder::Ascending),
+                            };
+                            let column = self.shown_columns.iter().find(|col| col.key() == key)?;
+                            Some(SortKey {
+                                column: column.clone(),
+                                order,
+                            })
+                        })
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Keeps the URL fragment in sync with the search, so the address bar is a share link.
+    #[cfg(target_arch = "wasm32")]
+    fn update_location(&mut self) {
+        let fragment = self.to_fragment();
+        if fragment != self.location_fragment {
+            if let Some(window) = web_sys::window() {
+                // replace instead of setting the hash to not flood the history
+                if let Err(e) = window.location().replace(&format!("#{fragment}")) {
+                    log::warn!("could not update location: {e:?}");
+                }
+            }
+            self.location_fragment = fragment;
+        }
+    }
+
+    /// Exports the shown spells with the visible columns, in the current sort order.
+    fn export(&self, format: ExportFormat) {
+        let Some(shown) = &self.shown_value else {
+            return;
+        };
+        let columns: Vec<&ColType> = self
+            .shown_columns
+            .iter()
+            .filter(|col| col.get_bool())
+            .collect();
+        let headers: Vec<String> = columns.iter().map(|col| col.title()).collect();
+        let values: Vec<Vec<Cow<'_, str>>> = shown
+            .iter()
+            .map(|(spell, level)| {
+                columns
+                    .iter()
+                    .map(|col| col.value(spell, level, self.caster_level, &self.query))
+                    .collect()
+            })
+            .collect();
+        let rows: Vec<Vec<&str>> = values
+            .iter()
+            .map(|row| row.iter().map(AsRef::as_ref).collect())
+            .collect();
+        format.save(&format.render(&headers, &rows));
+    }
+
+    /// Size selection and the sets of spells printable cards can be made of.
+    fn cards_ui(&mut self, ui: &mut egui::Ui) {
+        for size in CardSize::get_all() {
+            ui.radio_value(&mut self.card_size, size, size.title());
+        }
+        ui.separator();
+        let mut spells: Option<Vec<&'static Spell>> = None;
+        if ui.button("Shown spells").clicked() {
+            spells = self
+                .shown_value
+                .as_ref()
+                .map(|shown| shown.iter().map(|(spell, _)| *spell).collect());
+        }
+        for book in &self.spellbook_window.books {
+            if ui.button(format!("Spellbook: {}", book.name)).clicked() {
+                spells = Some(book.spells().into_iter().map(|(spell, _)| spell).collect());
+            }
+        }
+        if let Some(spells) = spells {
+            export::save("spell-cards.html", &cards::render(&spells, self.card_size));
+            ui.close_menu();
+        }
+    }
+
+    fn presets_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::text_edit::TextEdit::singleline(&mut self.preset_name)
+                    .hint_text("preset name")
+                    .desired_width(150.0),
+            );
+            let name = self.preset_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                .on_hover_text("save the current filters, replacing a preset with the same name")
+                .clicked()
+            {
+                let preset = Preset {
+                    name: name.to_string(),
+                    query: self.query.clone(),
+                };
+                match self.presets.iter_mut().find(|p| p.name == preset.name) {
+                    Some(p) => *p = preset,
+                    None => self.presets.push(preset),
+                }
+                self.preset_name.clear();
+            }
+        });
+        if !self.presets.is_empty() {
+            ui.separator();
+        }
+
+        let mut load = None;
+        let mut delete = None;
+        for (i, preset) in self.presets.iter_mut().enumerate() {
+            ui.horizontal(|ui| match &mut self.renaming_preset {
+                Some((j, name)) if *j == i => {
+                    let resp = ui.text_edit_singleline(name);
+                    if ui.button("✔").clicked()
+                        || (resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                    {
+                        if !name.trim().is_empty() {
+                            preset.name = name.trim().to_string();
+                        }
+                        self.renaming_preset = None;
+                    }
+                }
+                _ => {
+                    if ui.button(&preset.name).clicked() {
+                        load = Some(i);
+                    }
+                    if ui.button("✏").on_hover_text("rename").clicked() {
+                        self.renaming_preset = Some((i, preset.name.clone()));
+                    }
+                    if ui.button("🗑").on_hover_text("delete").clicked() {
+                        delete = Some(i);
+                    }
+                }
+            });
+        }
+
+        if let Some(i) = load {
+            self.query = self.presets[i].query.clone();
+            self.query.add_missing_filters();
+            self.query.update_keywords();
+            self.filter_window.filters_changed = true;
+            ui.close_menu();
+        }
+        if let Some(i) = delete {
+            self.presets.remove(i);
+            self.renaming_preset = None;
         }
     }
 
@@ -445,17 +1009,29 @@ impl SpellTable {
             let s: &mut egui::Style = ui.style_mut();
             s.wrap_mode = Some(egui::TextWrapMode::Extend);
 
+            if !self.suggestions.is_empty() {
+                let mut picked = None;
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("No spells found, did you mean:");
+                    for name in &self.suggestions {
+                        if ui.link(*name).clicked() {
+                            picked = Some(*name);
+                        }
+                    }
+                });
+                if let Some(name) = picked {
+                    self.query.name = name.to_string();
+                    self.filter_window.filters_changed = true;
+                }
+            }
+
             TableBuilder::new(ui)
                 .auto_shrink(false)
                 .sense(egui::Sense::click())
                 .striped(true)
                 .columns(
                     Column::auto().resizable(true),
-                    self.shown_columns
-                        .iter()
-                        .filter(|(x, _)| x.get_bool())
-                        .count()
-                        - 1,
+                    self.shown_columns.iter().filter(|x| x.get_bool()).count() - 1,
                 )
                 .column(Column::remainder())
                 .header(20.0, |mut header: TableRow<'_, '_>| {
@@ -468,10 +1044,12 @@ impl SpellTable {
     }
 
     fn render_header(&mut self, header: &mut egui_extras::TableRow<'_, '_>) {
-        let mut clicked = ColType::None;
-        for (col, order) in &mut self.shown_columns {
+        let mut clicked = None;
+        for col in &self.shown_columns {
             if col.get_bool() {
                 header.col(|ui| {
+                    let priority = self.sort_keys.iter().position(|k| k.column.same(col));
+                    let order = priority.map_or(RowOrder::None, |i| self.sort_keys[i].order);
                     let resp = ui
                         .horizontal(|ui| {
                             let btn = match order {
@@ -483,22 +1061,23 @@ impl SpellTable {
                                     egui::Button::new("⏶").fill(ui.visuals().selection.bg_fill)
                                 }
                             };
-                            let resp = ui.add(btn);
+                            let resp = ui
+                                .add(btn)
+                                .on_hover_text("shift-click to sort by several columns");
+                            if let Some(i) = priority.filter(|_| self.sort_keys.len() > 1) {
+                                ui.label(RichText::new((i + 1).to_string()).small());
+                            }
                             ui.add(
                                 egui::Label::new(RichText::new(col.title()).heading())
                                     .selectable(false),
                             );
 
-                            if clicked != ColType::None {
-                                *order = RowOrder::None;
-                            }
+                            let shift = ui.input(|input| input.modifiers.shift);
                             if resp.clicked() {
-                                *order = order.n();
-                                clicked = col.clone();
+                                clicked = Some((col.clone(), order.n(), shift));
                             }
                             if resp.secondary_clicked() {
-                                *order = order.p();
-                                clicked = col.clone();
+                                clicked = Some((col.clone(), order.p(), shift));
                             }
                         })
                         .response;
@@ -508,10 +1087,8 @@ impl SpellTable {
                             resp.context_menu(|ui| {
                                 if ui
                                     .add(
-                                        egui::text_edit::TextEdit::singleline(
-                                            &mut self.filter_window.name,
-                                        )
-                                        .hint_text("case insensitive search"),
+                                        egui::text_edit::TextEdit::singleline(&mut self.query.name)
+                                            .hint_text("case insensitive, typos are tolerated"),
                                     )
                                     .changed()
                                 {
@@ -523,41 +1100,58 @@ impl SpellTable {
                             resp.context_menu(|ui| {
                                 filter_row!(
                                     ui,
-                                    self.filter_window,
+                                    self.query,
                                     school,
                                     school_or,
+                                    self.filter_window.filters_changed,
                                     "Spellschool"
                                 );
                             });
                         }
                         ColType::Level(_) => {
                             resp.context_menu(|ui| {
-                                filter_row!(ui, self.filter_window, level, level_or, "Spell Level");
+                                filter_row!(
+                                    ui,
+                                    self.query,
+                                    level,
+                                    level_or,
+                                    self.filter_window.filters_changed,
+                                    "Spell Level"
+                                );
                             });
                         }
                         ColType::Subschools(_) => {
                             resp.context_menu(|ui| {
                                 filter_row!(
                                     ui,
-                                    self.filter_window,
+                                    self.query,
                                     subschool,
                                     subschool_or,
+                                    self.filter_window.filters_changed,
                                     "Subschool"
                                 );
                             });
                         }
                         ColType::Domain(_) => {
                             resp.context_menu(|ui| {
-                                filter_row!(ui, self.filter_window, domain, domain_or, "Domain");
+                                filter_row!(
+                                    ui,
+                                    self.query,
+                                    domain,
+                                    domain_or,
+                                    self.filter_window.filters_changed,
+                                    "Domain"
+                                );
                             });
                         }
                         ColType::Descriptors(_) => {
                             resp.context_menu(|ui| {
                                 filter_row!(
                                     ui,
-                                    self.filter_window,
+                                    self.query,
                                     descriptor,
                                     descriptor_or,
+                                    self.filter_window.filters_changed,
                                     "Descriptor"
                                 );
                             });
@@ -566,16 +1160,57 @@ impl SpellTable {
                             resp.context_menu(|ui| {
                                 filter_row!(
                                     ui,
-                                    self.filter_window,
+                                    self.query,
                                     components,
                                     components_or,
+                                    self.filter_window.filters_changed,
                                     "Components"
                                 );
                             });
                         }
                         ColType::Range(_) => {
                             resp.context_menu(|ui| {
-                                filter_row!(ui, self.filter_window, range, range_or, "Range");
+                                filter_row!(
+                                    ui,
+                                    self.query,
+                                    range,
+                                    range_or,
+                                    self.filter_window.filters_changed,
+                                    "Range"
+                                );
+                            });
+                        }
+                        ColType::RangeAtLevel(_) => {
+                            resp.context_menu(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Caster level");
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.caster_level).range(1..=20),
+                                    );
+                                });
+                            });
+                        }
+                        ColType::MaterialCost(_) => {
+                            resp.context_menu(|ui| {
+                                self.filter_window.max_cost_ui(ui, &mut self.query);
+                                if let Some(shown) = &self.shown_value {
+                                    let costs = material_cost_summary(
+                                        shown.iter().map(|(spell, _)| *spell),
+                                    );
+                                    ui.label(format!("Shown spells: {costs}"));
+                                }
+                            });
+                        }
+                        ColType::CastingTime(_) => {
+                            resp.context_menu(|ui| {
+                                filter_row!(
+                                    ui,
+                                    self.query,
+                                    casting_time,
+                                    casting_time_or,
+                                    self.filter_window.filters_changed,
+                                    "Casting Time"
+                                );
                             });
                         }
                         ColType::Area(_) => {}
@@ -584,59 +1219,121 @@ impl SpellTable {
                         ColType::Duration(_) => {}
                         ColType::SavingThrow(_) => {
                             resp.context_menu(|ui| {
-                                filter_row!(ui, self.filter_window, save, save_or, "Save");
+                                filter_row!(
+                                    ui,
+                                    self.query,
+                                    save,
+                                    save_or,
+                                    self.filter_window.filters_changed,
+                                    "Save"
+                                );
                             });
                         }
                         ColType::SpellResistance(_) => {
                             resp.context_menu(|ui| {
                                 filter_row!(
                                     ui,
-                                    self.filter_window,
+                                    self.query,
                                     spell_res,
                                     spell_res_or,
+                                    self.filter_window.filters_changed,
                                     "Spell Resistance"
                                 );
                             });
                         }
+                        ColType::Dismissible(_)
+                        | ColType::Shapeable(_)
+                        | ColType::Mythic(_)
+                        | ColType::Augmented(_) => {
+                            resp.context_menu(|ui| {
+                                filter_row!(
+                                    ui,
+                                    self.query,
+                                    flags,
+                                    flags_or,
+                                    self.filter_window.filters_changed,
+                                    "Flags"
+                                );
+                            });
+                        }
                         ColType::Description(_) => {
                             resp.context_menu(|ui| {
                                 ui.add(
                                     egui::text_edit::TextEdit::singleline(
-                                        &mut self.filter_window.description,
+                                        &mut self.query.description,
                                     )
                                     .hint_text("comma,seperated,keywords"),
                                 );
                             });
                         }
+                        ColType::Relevance(_) => {
+                            resp.context_menu(|ui| {
+                                if search_ui(ui, &mut self.query) {
+                                    self.filter_window.filters_changed = true;
+                                }
+                            });
+                        }
                         ColType::Source(_) => {}
                     };
                 });
-            } else if clicked != ColType::None {
-                *order = RowOrder::None;
             }
         }
-        if clicked != ColType::None {
-            for (col, order) in &mut self.shown_columns {
-                if clicked == *col {
-                    break;
-                }
-                *order = RowOrder::None;
+        if let Some((col, order, add)) = clicked {
+            self.set_sort(&col, order, add);
+        }
+    }
+
+    /// Sorts by `col`, with `add` the other keys are kept and `col` only breaks their ties.
+    fn set_sort(&mut self, col: &ColType, order: RowOrder, add: bool) {
+        if !add {
+            self.sort_keys.retain(|k| k.column.same(col));
+        }
+        match (
+            self.sort_keys.iter().position(|k| k.column.same(col)),
+            order,
+        ) {
+            (Some(i), RowOrder::None) => {
+                self.sort_keys.remove(i);
             }
+            (Some(i), order) => self.sort_keys[i].order = order,
+            (None, RowOrder::None) => {}
+            (None, order) => self.sort_keys.push(SortKey {
+                column: col.clone(),
+                order,
+            }),
         }
     }
 
     fn render_body(&mut self, body: egui_extras::TableBody<'_>) {
         if let Some(stuff) = &mut self.shown_value {
-            for (col, ordering) in &self.shown_columns {
-                stuff.sort_by(|(spell1, level1), (spell2, level2)| {
-                    ordering.compare(col, spell1, spell2, level1, level2)
-                });
-            }
+            stuff.sort_by(|(spell1, level1), (spell2, level2)| {
+                self.sort_keys
+                    .iter()
+                    .fold(std::cmp::Ordering::Equal, |ordering, key| {
+                        ordering.then_with(|| {
+                            key.order.compare(
+                                &key.column,
+                                spell1,
+                                spell2,
+                                level1,
+                                level2,
+                                &self.query,
+                            )
+                        })
+                    })
+                    // otherwise equal rows are ranked by the full text search
+                    .then_with(|| {
+                        sorting::cmp_known(
+                            self.query.relevance(spell1).map(Reverse),
+                            self.query.relevance(spell2).map(Reverse),
+                        )
+                    })
+            });
 
             body.rows(15.0, stuff.len(), |mut row: TableRow<'_, '_>| {
                 let (spell, level) = &stuff[row.index()];
                 //row.set_selected(selected);
-                for (col, _) in &self.shown_columns {
+                for col in &self.shown_columns {
                     if col.get_bool() {
                         match col {
                             ColType::None => {}
@@ -698,6 +1395,18 @@ impl SpellTable {
                                     );
                                 });
                             }
+                            ColType::RangeAtLevel(_) => {
+                                row.col(|ui| {
+                                    let range =
+                                        col.value(spell, level, self.caster_level, &self.query);
+                                    ui.add(
+                                        egui::Label::new(range.as_ref())
+                                            .truncate()
+                                            .selectable(false),
+                                    )
+                                    .on_hover_text(&spell.range);
+                                });
+                            }
                             ColType::Area(_) => {
                                 row.col(|ui| {
                                     ui.add(
@@ -714,6 +1423,22 @@ impl SpellTable {
                                     );
                                 });
                             }
+                            ColType::MaterialCost(_) => {
+                                row.col(|ui| {
+                                    let cost =
+                                        col.value(spell, level, self.caster_level, &self.query);
+                                    ui.add(egui::Label::new(cost.as_ref()).selectable(false));
+                                });
+                            }
+                            ColType::CastingTime(_) => {
+                                row.col(|ui| {
+                                    ui.add(
+                                        egui::Label::new(&spell.casting_time)
+                                            .truncate()
+                                            .selectable(false),
+                                    );
+                                });
+                            }
                             ColType::Targets(_) => {
                                 row.col(|ui| {
                                     ui.add(
@@ -750,15 +1475,36 @@ impl SpellTable {
                                     );
                                 });
                             }
+                            ColType::Dismissible(_)
+                            | ColType::Shapeable(_)
+                            | ColType::Mythic(_)
+                            | ColType::Augmented(_) => {
+                                row.col(|ui| {
+                                    let flag =
+                                        col.value(spell, level, self.caster_level, &self.query);
+                                    ui.add(egui::Label::new(flag.as_ref()).selectable(false));
+                                });
+                            }
                             ColType::Description(_) => {
                                 row.col(|ui| {
+                                    let description = highlight(
+                                        ui,
+                                        &spell.short_description,
+                                        &self.query,
+                                        body_format(ui),
+                                    );
                                     ui.add(
-                                        egui::Label::new(&spell.short_description)
-                                            .truncate()
-                                            .selectable(false),
+                                        egui::Label::new(description).truncate().selectable(false),
                                     );
                                 });
                             }
+                            ColType::Relevance(_) => {
+                                row.col(|ui| {
+                                    let relevance =
+                                        col.value(spell, level, self.caster_level, &self.query);
+                                    ui.add(egui::Label::new(relevance.as_ref()).selectable(false));
+                                });
+                            }
                             ColType::Source(_) => {
                                 row.col(|ui| {
                                     ui.add(
@@ -784,14 +1530,20 @@ impl SpellTable {
                         self.selected_spell = Some(new_spell.clone())
                     }
                 }
-                if row_response.secondary_clicked() {
-                    self.selected_spell_windows.push((
-                        new_spell.clone(),
-                        true,
-                        Id::new(self.counter),
-                    ));
-                    self.counter += 1;
-                }
+                row_response.context_menu(|ui| {
+                    if ui.button("Open in window").clicked() {
+                        self.selected_spell_windows.push((
+                            new_spell.clone(),
+                            true,
+                            Id::new(self.counter),
+                        ));
+                        self.counter += 1;
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Spellbooks", |ui| {
+                        self.spellbook_window.add_menu_ui(ui, new_spell);
+                    });
+                });
             });
         }
     }
@@ -804,7 +1556,9 @@ impl SpellTable {
                 .show(ctx, |ui| {
                     egui::containers::ScrollArea::vertical()
                         .auto_shrink(false)
-                        .show(ui, |ui| render_spell(ui, old_spell))
+                        .show(ui, |ui| {
+                            render_spell(ui, old_spell, &mut self.spellbook_window, &self.query)
+                        })
                         .inner
                 })
                 .inner;
@@ -826,22 +1580,25 @@ impl SpellTable {
     }
 
     fn update_filters(&mut self) -> bool {
-        if self.filter_window.filters_changed || self.source_window.filters_changed {
-            self.shown_value = Some(
-                crate::spell::ALL_SPELLS
-                    .iter()
-                    .filter_map(|spell| {
-                        spell.filter_map_level(
-                            self.filter_window.class_or,
-                            &self.filter_window.selected_classes,
-                        )
-                    })
-                    .filter(|(spell, level)| self.filter_window.test(spell, level))
-                    .filter(|(spell, _)| self.source_window.test(spell))
-                    .collect(),
-            );
+        if self.filter_window.filters_changed
+            || self.source_window.filters_changed
+            || self.spellbook_window.filters_changed
+        {
+            let mut shown = self.query.run();
+            shown.retain(|(spell, _)| self.spellbook_window.test(spell));
+            self.suggestions = if shown.is_empty() && !self.query.name.trim().is_empty() {
+                self.query.suggestions(5)
+            } else {
+                Vec::new()
+            };
+            self.shown_value = Some(shown);
+            if !self.query_text_focused {
+                self.query_text = self.query.to_query_string();
+                self.query_error = None;
+            }
             self.filter_window.filters_changed = false;
             self.source_window.filters_changed = false;
+            self.spellbook_window.filters_changed = false;
             true
         } else {
             false
@@ -849,7 +1606,53 @@ impl SpellTable {
     }
 }
 
-fn render_spell(ui: &mut egui::Ui, spell: &mut Spell) -> Option<(Spell, bool)> {
+/// Filters saved under a name, e.g. for every character of a group.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Preset {
+    name: String,
+    query: SpellQuery,
+}
+
+/// Lays out `text` like a normal text edit, but marks the `error` byte range.
+fn error_layout_job(
+    ui: &egui::Ui,
+    text: &str,
+    error: Option<std::ops::Range<usize>>,
+) -> egui::text::LayoutJob {
+    let format = egui::TextFormat::simple(
+        egui::TextStyle::Body.resolve(ui.style()),
+        ui.visuals().text_color(),
+    );
+    let error_format = egui::TextFormat {
+        color: ui.visuals().error_fg_color,
+        underline: egui::Stroke::new(1.0, ui.visuals().error_fg_color),
+        ..format.clone()
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    match error.and_then(|span| {
+        Some((
+            text.get(..span.start)?,
+            text.get(span.clone())?,
+            text.get(span.end..)?,
+        ))
+    }) {
+        Some((before, marked, after)) => {
+            job.append(before, 0.0, format.clone());
+            job.append(marked, 0.0, error_format);
+            job.append(after, 0.0, format);
+        }
+        None => job.append(text, 0.0, format),
+    }
+    job
+}
+
+fn render_spell(
+    ui: &mut egui::Ui,
+    spell: &mut Spell,
+    spellbooks: &mut SpellbookWindow,
+    query: &SpellQuery,
+) -> Option<(Spell, bool)> {
     let meta: &SpellMeta = BONUS_INFO.get(&spell.id).unwrap();
 
     ui.horizontal_wrapped(|ui| {
@@ -868,6 +1671,9 @@ fn render_spell(ui: &mut egui::Ui, spell: &mut Spell) -> Option<(Spell, bool)> {
             )
             .open_in_new_tab(true),
         );
+        ui.menu_button("📖", |ui| spellbooks.add_menu_ui(ui, spell))
+            .response
+            .on_hover_text("add to spellbook");
     });
     ui.horizontal_wrapped(|ui| {
         ui.label(egui::RichText::new("School").strong().size(12.0));
@@ -963,7 +1769,7 @@ fn render_spell(ui: &mut egui::Ui, spell: &mut Spell) -> Option<(Spell, bool)> {
         });
     }
     ui.separator();
-    let r = html2egui(&meta.description_struct, ui);
+    let r = html2egui(&meta.description_struct, ui, query);
 
     if spell.mythic {
         ui.separator();
@@ -983,77 +1789,88 @@ fn render_spell(ui: &mut egui::Ui, spell: &mut Spell) -> Option<(Spell, bool)> {
     r
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(from = "StoredFilterWindow")]
 struct FilterWindow {
-    name: String,
-    school: Vec<Spellschool>,
-    school_or: bool,
-    level: Vec<Level>,
-    level_or: bool,
-    subschool: Vec<Subschool>,
-    subschool_or: bool,
-    domain: Vec<Domain>,
-    domain_or: bool,
-    descriptor: Vec<SpellDescriptor>,
-    descriptor_or: bool,
-    components: Vec<SpellComponent>,
-    components_or: bool,
-    range: Vec<SpellRange>,
-    range_or: bool,
-    area: String,
-    effect: String,
-    targets: String,
-    duration: String,
-    save: Vec<Save>,
-    save_or: bool,
-    spell_res: Vec<SpellResistance>,
-    spell_res_or: bool,
-    description: String,
     prev_description: String,
-    #[serde(skip)]
-    keywords: Vec<Result<Regex, regex::Error>>,
-    source: Vec<SpellSource>,
-    source_or: bool,
-    selected_classes: Vec<ClassType>,
-    class_or: bool,
     #[serde(skip, default)]
     filters_changed: bool,
+    /// Filters of a state saved before they moved into [`SpellQuery`].
+    #[serde(skip)]
+    legacy: Option<SpellQuery>,
 }
 
-impl Default for FilterWindow {
-    fn default() -> Self {
+/// A saved [`FilterWindow`], older versions kept the filters in it.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct StoredFilterWindow {
+    prev_description: String,
+    name: Option<String>,
+    school: Option<Vec<Spellschool>>,
+    school_or: Option<bool>,
+    level: Option<Vec<Level>>,
+    level_or: Option<bool>,
+    subschool: Option<Vec<Subschool>>,
+    subschool_or: Option<bool>,
+    domain: Option<Vec<Domain>>,
+    domain_or: Option<bool>,
+    descriptor: Option<Vec<SpellDescriptor>>,
+    descriptor_or: Option<bool>,
+    components: Option<Vec<SpellComponent>>,
+    components_or: Option<bool>,
+    range: Option<Vec<SpellRange>>,
+    range_or: Option<bool>,
+    save: Option<Vec<Save>>,
+    save_or: Option<bool>,
+    spell_res: Option<Vec<SpellResistance>>,
+    spell_res_or: Option<bool>,
+    description: Option<String>,
+    selected_classes: Option<Vec<ClassType>>,
+    class_or: Option<bool>,
+}
+
+impl From<StoredFilterWindow> for FilterWindow {
+    fn from(stored: StoredFilterWindow) -> Self {
+        let mut query = SpellQuery::new();
+        let mut migrated = false;
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(
+                    if let Some(value) = stored.$field {
+                        query.$field = value;
+                        migrated = true;
+                    }
+                )*
+            };
+        }
+        take!(
+            name,
+            school,
+            school_or,
+            level,
+            level_or,
+            subschool,
+            subschool_or,
+            domain,
+            domain_or,
+            descriptor,
+            descriptor_or,
+            components,
+            components_or,
+            range,
+            range_or,
+            save,
+            save_or,
+            spell_res,
+            spell_res_or,
+            description,
+            selected_classes,
+            class_or
+        );
         Self {
-            name: String::new(),
-            school: Spellschool::get_all(),
-            school_or: false,
-            level: Level::get_all(),
-            level_or: false,
-            subschool: Subschool::get_all(),
-            subschool_or: false,
-            domain: Domain::get_all(),
-            domain_or: false,
-            descriptor: SpellDescriptor::get_all(),
-            descriptor_or: false,
-            components: SpellComponent::get_all(),
-            components_or: false,
-            range: SpellRange::get_all(),
-            range_or: false,
-            area: String::new(),
-            effect: String::new(),
-            targets: String::new(),
-            duration: String::new(),
-            save: Save::get_all(),
-            save_or: false,
-            spell_res: SpellResistance::get_all(),
-            spell_res_or: false,
-            description: String::new(),
-            prev_description: String::new(),
-            keywords: Vec::new(),
-            source: SpellSource::get_all(),
-            source_or: false,
-            selected_classes: ClassType::get_all(),
-            class_or: false,
+            prev_description: stored.prev_description,
             filters_changed: false,
+            legacy: migrated.then_some(query),
         }
     }
 }
@@ -1063,7 +1880,7 @@ impl FilterWindow {
         Default::default()
     }
 
-    fn filter_ui(&mut self, ctx: &egui::Context, filter_open: &mut bool) {
+    fn filter_ui(&mut self, ctx: &egui::Context, filter_open: &mut bool, query: &mut SpellQuery) {
         egui::containers::Window::new("Filters")
             .open(filter_open)
             .show(ctx, |ui| {
@@ -1071,8 +1888,8 @@ impl FilterWindow {
                 ui.horizontal(|ui| {
                     if ui
                         .add(
-                            egui::text_edit::TextEdit::singleline(&mut self.name)
-                                .hint_text("case insensitive search"),
+                            egui::text_edit::TextEdit::singleline(&mut query.name)
+                                .hint_text("case insensitive, typos are tolerated"),
                         )
                         .changed()
                     {
@@ -1083,157 +1900,292 @@ impl FilterWindow {
                 ui.label("Description");
                 ui.horizontal(|ui| {
                     ui.add(
-                        egui::text_edit::TextEdit::singleline(&mut self.description)
-                            .hint_text("comma,seperated,keywords"),
+                        egui::text_edit::TextEdit::singleline(&mut query.description)
+                            .hint_text("fire, -evil, acid|cold, \"a phrase\", /regex/"),
                     );
                 });
+                if let Some(e) = query.keyword_error() {
+                    // regex errors point at the mistake with a caret, keep it aligned
+                    ui.label(
+                        egui::RichText::new(e)
+                            .monospace()
+                            .color(ui.visuals().error_fg_color),
+                    );
+                }
                 ui.separator();
-                filter_row!(ui, self, selected_classes, class_or, "Class");
+                ui.label("Full Text Search");
+                if search_ui(ui, query) {
+                    self.filters_changed = true;
+                }
+                ui.separator();
+                filter_row!(
+                    ui,
+                    query,
+                    selected_classes,
+                    class_or,
+                    self.filters_changed,
+                    "Class"
+                );
+                ui.separator();
+                filter_row!(
+                    ui,
+                    query,
+                    school,
+                    school_or,
+                    self.filters_changed,
+                    "Spellschool"
+                );
+                ui.separator();
+                filter_row!(
+                    ui,
+                    query,
+                    level,
+                    level_or,
+                    self.filters_changed,
+                    "Spell Level"
+                );
+                self.level_range_ui(ui, query);
+                ui.separator();
+                filter_row!(
+                    ui,
+                    query,
+                    components,
+                    components_or,
+                    self.filters_changed,
+                    "Components"
+                );
+                self.max_cost_ui(ui, query);
+                ui.separator();
+                filter_row!(
+                    ui,
+                    query,
+                    casting_time,
+                    casting_time_or,
+                    self.filters_changed,
+                    "Casting Time"
+                );
                 ui.separator();
-                filter_row!(ui, self, school, school_or, "Spellschool");
+                filter_row!(ui, query, range, range_or, self.filters_changed, "Range");
+                self.min_range_ui(ui, query);
                 ui.separator();
-                filter_row!(ui, self, level, level_or, "Spell Level");
+                filter_row!(
+                    ui,
+                    query,
+                    duration,
+                    duration_or,
+                    self.filters_changed,
+                    "Duration"
+                );
+                self.min_duration_ui(ui, query);
                 ui.separator();
-                filter_row!(ui, self, components, components_or, "Components");
+                filter_row!(ui, query, area, area_or, self.filters_changed, "Area");
+                self.min_area_ui(ui, query);
                 ui.separator();
-                filter_row!(ui, self, range, range_or, "Range");
+                filter_row!(
+                    ui,
+                    query,
+                    targets,
+                    targets_or,
+                    self.filters_changed,
+                    "Targets"
+                );
                 ui.separator();
-                filter_row!(ui, self, subschool, subschool_or, "Subschool");
+                filter_row!(
+                    ui,
+                    query,
+                    subschool,
+                    subschool_or,
+                    self.filters_changed,
+                    "Subschool"
+                );
                 ui.separator();
-                filter_row!(ui, self, domain, domain_or, "Domain");
+                filter_row!(ui, query, domain, domain_or, self.filters_changed, "Domain");
                 ui.separator();
-                filter_row!(ui, self, save, save_or, "Save");
+                filter_row!(ui, query, save, save_or, self.filters_changed, "Save");
                 ui.separator();
-                filter_row!(ui, self, spell_res, spell_res_or, "Spell Resistance");
+                filter_row!(
+                    ui,
+                    query,
+                    spell_res,
+                    spell_res_or,
+                    self.filters_changed,
+                    "Spell Resistance"
+                );
                 ui.separator();
-                filter_row!(ui, self, descriptor, descriptor_or, "Descriptor");
+                filter_row!(
+                    ui,
+                    query,
+                    descriptor,
+                    descriptor_or,
+                    self.filters_changed,
+                    "Descriptor"
+                );
+                ui.separator();
+                filter_row!(ui, query, flags, flags_or, self.filters_changed, "Flags");
+                ui.separator();
+                ui.collapsing("Expression", |ui| {
+                    if query.expr.editor_ui(ui) {
+                        self.filters_changed = true;
+                    }
+                });
             });
     }
 
-    fn test(&self, spell: &Spell, level: &str) -> bool {
-        spell
-            .name
-            .to_lowercase()
-            .contains(&self.name.to_lowercase())
-            && keyword_match(&spell.description.to_lowercase(), &self.keywords)
-            && if self.level_or {
-                let mut it = self.level.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(level) || it.any(|f| f.test(level))
-                } else {
-                    true
-                }
-            } else {
-                self.level.iter().all(|f| f.test(level))
+    fn min_range_ui(&mut self, ui: &mut egui::Ui, query: &mut SpellQuery) {
+        ui.horizontal(|ui| {
+            let mut enabled = query.min_range.is_some();
+            if ui.checkbox(&mut enabled, "At least").changed() {
+                query.min_range = enabled.then(MinRange::default);
+                self.filters_changed = true;
             }
-            && if self.school_or {
-                let mut it = self.school.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.school) || it.any(|f| f.test(&spell.school))
-                } else {
-                    true
+            if let Some(range) = &mut query.min_range {
+                if min_range_ui(ui, range) {
+                    self.filters_changed = true;
                 }
-            } else {
-                self.school.iter().all(|f| f.test(&spell.school))
             }
-            && if self.subschool_or {
-                let mut it = self.subschool.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.subschool) || it.any(|f| f.test(&spell.subschool))
-                } else {
-                    true
-                }
-            } else {
-                self.subschool.iter().all(|f| f.test(&spell.subschool))
+        });
+    }
+
+    fn min_duration_ui(&mut self, ui: &mut egui::Ui, query: &mut SpellQuery) {
+        ui.horizontal(|ui| {
+            let mut enabled = query.min_duration.is_some();
+            if ui.checkbox(&mut enabled, "At least").changed() {
+                query.min_duration = enabled.then(MinDuration::default);
+                self.filters_changed = true;
             }
-            && if self.domain_or {
-                let mut it = self.domain.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.domain) || it.any(|f| f.test(&spell.domain))
-                } else {
-                    true
+            if let Some(duration) = &mut query.min_duration {
+                if min_duration_ui(ui, ui.id().with("min_duration"), duration) {
+                    self.filters_changed = true;
                 }
-            } else {
-                self.domain.iter().all(|f| f.test(&spell.domain))
             }
-            && if self.save_or {
-                let mut it = self.save.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.saving_throw) || it.any(|f| f.test(&spell.saving_throw))
-                } else {
-                    true
-                }
-            } else {
-                self.save.iter().all(|f| f.test(&spell.saving_throw))
+        });
+    }
+
+    fn max_cost_ui(&mut self, ui: &mut egui::Ui, query: &mut SpellQuery) {
+        ui.horizontal(|ui| {
+            let mut enabled = query.max_cost.is_some();
+            if ui.checkbox(&mut enabled, "Material cost at most").changed() {
+                query.max_cost = enabled.then_some(0);
+                self.filters_changed = true;
             }
-            && if self.spell_res_or {
-                let mut it = self.spell_res.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.spell_resistance) || it.any(|f| f.test(&spell.spell_resistance))
-                } else {
-                    true
+            if let Some(cost) = &mut query.max_cost {
+                if ui
+                    .add(egui::DragValue::new(cost).range(0..=100_000).suffix(" gp"))
+                    .changed()
+                {
+                    self.filters_changed = true;
                 }
-            } else {
-                self.spell_res
-                    .iter()
-                    .all(|f| f.test(&spell.spell_resistance))
             }
-            && if self.components_or {
-                let mut it = self.components.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.special_test(spell) || it.any(|f| f.special_test(spell))
-                } else {
-                    true
-                }
-            } else {
-                self.components.iter().all(|f| f.special_test(spell))
+        });
+    }
+
+    fn min_area_ui(&mut self, ui: &mut egui::Ui, query: &mut SpellQuery) {
+        ui.horizontal(|ui| {
+            let mut enabled = query.min_area.is_some();
+            if ui.checkbox(&mut enabled, "At least").changed() {
+                query.min_area = enabled.then(MinArea::default);
+                self.filters_changed = true;
             }
-            && if self.descriptor_or {
-                let mut it = self.descriptor.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.descriptors) || it.any(|f| f.test(&spell.descriptors))
-                } else {
-                    true
+            if let Some(area) = &mut query.min_area {
+                if min_area_ui(ui, area) {
+                    self.filters_changed = true;
                 }
-            } else {
-                self.descriptor.iter().all(|f| f.test(&spell.descriptors))
             }
-            && if self.range_or {
-                let mut it = self.range.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.range) || it.any(|f| f.test(&spell.range))
-                } else {
-                    true
-                }
-            } else {
-                self.range.iter().all(|f| f.test(&spell.range))
+        });
+    }
+
+    fn level_range_ui(&mut self, ui: &mut egui::Ui, query: &mut SpellQuery) {
+        ui.horizontal(|ui| {
+            ui.label("Level Ranges");
+            if ui
+                .button("+")
+                .on_hover_text("add a numeric level range")
+                .clicked()
+            {
+                query.level_ranges.push(LevelRange::default());
+                self.filters_changed = true;
             }
-            && if self.source_or {
-                let mut it = self.source.iter().filter(|f| f.some_filter());
-                if let Some(ff) = it.next() {
-                    ff.test(&spell.source) || it.any(|f| f.test_exact(&spell.source))
-                } else {
-                    true
+        });
+        let mut removed = None;
+        for (i, range) in query.level_ranges.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt(("level_range", i))
+                    .selected_text(
+                        range
+                            .class
+                            .as_ref()
+                            .map_or("Any class".to_string(), |c| c.title()),
+                    )
+                    .height(300.0)
+                    .show_ui(ui, |ui| {
+                        if ui
+                            .selectable_value(&mut range.class, None, "Any class")
+                            .on_hover_text("the levels shown in the table")
+                            .changed()
+                        {
+                            self.filters_changed = true;
+                        }
+                        for class in ClassType::get_all() {
+                            let class = class.with_state(FilterState::Positive);
+                            let title = class.title();
+                            if ui
+                                .selectable_value(&mut range.class, Some(class), title)
+                                .changed()
+                            {
+                                self.filters_changed = true;
+                            }
+                        }
+                    });
+                ui.label("from");
+                if ui
+                    .add(egui::DragValue::new(&mut range.min).range(0..=9))
+                    .changed()
+                {
+                    range.max = range.max.max(range.min);
+                    self.filters_changed = true;
                 }
-            } else {
-                self.source.iter().all(|f| f.test_exact(&spell.source))
-            }
+                ui.label("to");
+                if ui
+                    .add(egui::DragValue::new(&mut range.max).range(range.min..=9))
+                    .changed()
+                {
+                    self.filters_changed = true;
+                }
+                if ui.button("🗑").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            query.level_ranges.remove(i);
+            self.filters_changed = true;
+        }
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(from = "StoredSourceWindow")]
 struct SourceWindow {
-    source: Vec<SpellSource>,
-    source_or: bool,
+    #[serde(skip, default)]
     filters_changed: bool,
+    /// Source filter of a state saved before it moved into [`SpellQuery`].
+    #[serde(skip)]
+    legacy: Option<(Vec<SpellSource>, bool)>,
 }
 
-impl Default for SourceWindow {
-    fn default() -> Self {
+/// A saved [`SourceWindow`], older versions kept the source filter in it.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct StoredSourceWindow {
+    source: Option<Vec<SpellSource>>,
+    source_or: bool,
+}
+
+impl From<StoredSourceWindow> for SourceWindow {
+    fn from(stored: StoredSourceWindow) -> Self {
         Self {
-            source: SpellSource::get_all(),
-            source_or: false,
             filters_changed: false,
+            legacy: stored.source.map(|source| (source, stored.source_or)),
         }
     }
 }
@@ -1243,26 +2195,21 @@ impl SourceWindow {
         Default::default()
     }
 
-    fn filter_ui(&mut self, ctx: &egui::Context, filter_open: &mut bool) {
+    fn filter_ui(
+        &mut self,
+        ctx: &egui::Context,
+        filter_open: &mut bool,
+        query: &mut SpellQuery,
+        spellbooks: &mut SpellbookWindow,
+    ) {
         egui::containers::Window::new("Sources")
             .open(filter_open)
             .show(ctx, |ui| {
-                filter_row!(ui, self, source, source_or, "");
+                spellbooks.source_filter_ui(ui);
+                ui.separator();
+                filter_row!(ui, query, source, source_or, self.filters_changed, "");
             });
     }
-
-    fn test(&self, spell: &Spell) -> bool {
-        if self.source_or {
-            let mut it = self.source.iter().filter(|f| f.some_filter());
-            if let Some(ff) = it.next() {
-                ff.test(&spell.source) || it.any(|f| f.test_exact(&spell.source))
-            } else {
-                true
-            }
-        } else {
-            self.source.iter().all(|f| f.test_exact(&spell.source))
-        }
-    }
 }
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
@@ -1279,6 +2226,8 @@ impl SpellWindow {
         filter_open: &mut bool,
         spell: &mut Spell,
         id: Id,
+        spellbooks: &mut SpellbookWindow,
+        query: &SpellQuery,
     ) -> Option<(Spell, bool)> {
         if let Some(r) = egui::containers::Window::new(&spell.name)
             .id(id)
@@ -1286,7 +2235,7 @@ impl SpellWindow {
             .show(ctx, |ui| {
                 egui::containers::ScrollArea::vertical()
                     .auto_shrink(false)
-                    .show(ui, |ui| render_spell(ui, spell))
+                    .show(ui, |ui| render_spell(ui, spell, spellbooks, query))
                     .inner
             })
         {
@@ -1296,15 +2245,3 @@ impl SpellWindow {
         }
     }
 }
-
-fn keyword_match(haystack: &str, keywords: &Vec<Result<Regex, regex::Error>>) -> bool {
-    let mut ret = true;
-    for word in keywords {
-        ret &= if let Ok(re) = word {
-            re.is_match(haystack)
-        } else {
-            false
-        };
-    }
-    ret
-}
@@ -0,0 +1,223 @@
+//! Printable spell cards as a standalone HTML page.
+
+use crate::spell::{Spell, SpellDescriptionStruct, BONUS_INFO};
+
+/// Smallest font size the text is shrunk to before it continues on another card.
+const MIN_FONT_PT: f32 = 5.5;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum CardSize {
+    #[default]
+    Poker,
+    Tarot,
+    A6,
+}
+
+impl CardSize {
+    pub fn get_all() -> [Self; 3] {
+        [Self::Poker, Self::Tarot, Self::A6]
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Poker => "Poker (63 × 88 mm)",
+            Self::Tarot => "Tarot (70 × 120 mm)",
+            Self::A6 => "A6 (105 × 148 mm)",
+        }
+    }
+
+    /// Width and height in millimeters.
+    fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Poker => (63, 88),
+            Self::Tarot => (70, 120),
+            Self::A6 => (105, 148),
+        }
+    }
+
+    fn font_pt(&self) -> f32 {
+        match self {
+            Self::Poker => 7.5,
+            Self::Tarot => 8.0,
+            Self::A6 => 9.5,
+        }
+    }
+}
+
+/// Renders one card per spell, text that doesn't fit is shrunk and then continued on
+/// further cards by a script when the page is opened.
+pub fn render(spells: &[&Spell], size: CardSize) -> String {
+    let (width, height) = size.dimensions();
+    let mut cards = String::new();
+    for spell in spells {
+        card(spell, &mut cards);
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Spell cards</title>
+<style>
+@page {{ margin: 8mm; }}
+body {{ margin: 0; font-family: Georgia, serif; }}
+.card {{
+  display: inline-flex; flex-direction: column; vertical-align: top;
+  box-sizing: border-box; width: {width}mm; height: {height}mm; margin: 1mm;
+  padding: 3mm; border: 0.3mm solid #000; border-radius: 3mm;
+  overflow: hidden; break-inside: avoid; font-size: {font}pt; line-height: 1.2;
+}}
+.card h1 {{ margin: 0; font-size: 1.5em; }}
+.card .stats {{ margin: 0.5em 0; padding: 0.3em 0; border-top: 0.2mm solid #000; border-bottom: 0.2mm solid #000; }}
+.card .stats div {{ margin: 0; }}
+.card .desc {{ flex: 1; overflow: hidden; }}
+.card p {{ margin: 0 0 0.4em; }}
+.card ul {{ margin: 0 0 0.4em; padding-left: 1.2em; }}
+.card table {{ border-collapse: collapse; margin: 0 0 0.4em; }}
+.card th, .card td {{ border: 0.2mm solid #888; padding: 0 0.3em; }}
+.card caption {{ font-weight: bold; }}
+.card .source {{ margin-top: 0.3em; font-size: 0.8em; text-align: right; }}
+</style>
+</head>
+<body>
+{cards}<script>
+const MIN_FONT_PT = {min_font};
+const overflows = (card) => card.scrollHeight > card.clientHeight + 1;
+const cards = document.getElementsByClassName("card");
+// the collection is live, so continuation cards get fitted as well
+for (let i = 0; i < cards.length; i++) {{
+  const card = cards[i];
+  let size = parseFloat(getComputedStyle(card).fontSize) * 0.75;
+  while (overflows(card) && size > MIN_FONT_PT) {{
+    size = Math.max(size - 0.25, MIN_FONT_PT);
+    card.style.fontSize = size + "pt";
+  }}
+  const desc = card.querySelector(".desc");
+  if (!overflows(card) || desc.children.length < 2) continue;
+  const next = document.createElement("div");
+  next.className = "card";
+  next.dataset.name = card.dataset.name;
+  next.innerHTML = "<h1></h1><div class=\"desc\"></div>";
+  next.querySelector("h1").textContent = card.dataset.name + " (cont.)";
+  const rest = next.querySelector(".desc");
+  while (overflows(card) && desc.children.length > 1) {{
+    rest.prepend(desc.lastElementChild);
+  }}
+  card.after(next);
+}}
+</script>
+</body>
+</html>
+"#,
+        font = size.font_pt(),
+        min_font = MIN_FONT_PT,
+    )
+}
+
+fn card(spell: &Spell, out: &mut String) {
+    let name = escape(&spell.name);
+    out.push_str(&format!(
+        "<div class=\"card\" data-name=\"{name}\">\n<h1>{name}</h1>\n<div class=\"stats\">\n"
+    ));
+
+    let mut school = spell.school.clone();
+    if !spell.subschool.is_empty() {
+        school += &format!(" ({})", spell.subschool);
+    }
+    if !spell.descriptors.is_empty() {
+        school += &format!(" [{}]", spell.descriptors);
+    }
+    stat(out, "School", &school);
+    stat(out, "Level", &spell.spell_level);
+    stat(out, "Domain", &spell.domain);
+    stat(out, "Bloodline", &spell.bloodline);
+    stat(out, "Casting Time", &spell.casting_time);
+    stat(out, "Components", &spell.components);
+    stat(out, "Range", &spell.range);
+    stat(out, "Area", &spell.area);
+    let shapeable = if spell.shapeable { " (S)" } else { "" };
+    if !spell.effect.is_empty() {
+        stat(out, "Effect", &format!("{}{shapeable}", spell.effect));
+    }
+    stat(out, "Target", &spell.targets);
+    let dismissible = if spell.dismissible && !spell.duration.ends_with("(D)") {
+        " (D)"
+    } else {
+        ""
+    };
+    stat(out, "Duration", &format!("{}{dismissible}", spell.duration));
+    stat(out, "Saving Throw", &spell.saving_throw);
+    stat(out, "Spell Resistance", &spell.spell_resistance);
+    out.push_str("</div>\n<div class=\"desc\">\n");
+
+    match BONUS_INFO.get(&spell.id) {
+        Some(meta) => description(&meta.description_struct, out),
+        None => out.push_str(&format!("<p>{}</p>", escape(&spell.description))),
+    }
+    if spell.mythic {
+        out.push_str(&format!(
+            "<p><b>Mythic:</b> {}</p>\n",
+            escape(&spell.mythic_text)
+        ));
+        if !spell.augmented.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", escape(&spell.augmented)));
+        }
+    }
+    out.push_str(&format!(
+        "</div>\n<div class=\"source\">{}</div>\n</div>\n",
+        escape(&spell.source)
+    ));
+}
+
+fn stat(out: &mut String, label: &str, value: &str) {
+    if !value.is_empty() {
+        out.push_str(&format!("<div><b>{label}</b> {}</div>\n", escape(value)));
+    }
+}
+
+/// Writes the description as HTML, every top level element is a block the overflow script
+/// can move to a continuation card.
+fn description(st: &SpellDescriptionStruct, out: &mut String) {
+    let tag = |tag: &str, children: &[SpellDescriptionStruct], out: &mut String| {
+        out.push_str(&format!("<{tag}>"));
+        for c in children {
+            description(c, out);
+        }
+        out.push_str(&format!("</{tag}>"));
+    };
+    match st {
+        SpellDescriptionStruct::Body(children) => {
+            for c in children {
+                description(c, out);
+                out.push('\n');
+            }
+        }
+        SpellDescriptionStruct::Paragraph(children) => {
+            if !children.is_empty() {
+                tag("p", children, out);
+            }
+        }
+        SpellDescriptionStruct::Caption(children) => tag("caption", children, out),
+        SpellDescriptionStruct::Sup(children) => tag("sup", children, out),
+        SpellDescriptionStruct::Listing(children) => tag("ul", children, out),
+        SpellDescriptionStruct::Line(children) => tag("li", children, out),
+        SpellDescriptionStruct::Table(children) => tag("table", children, out),
+        SpellDescriptionStruct::Thead(children) => tag("thead", children, out),
+        SpellDescriptionStruct::Tbody(children) => tag("tbody", children, out),
+        SpellDescriptionStruct::Tfoot(children) => tag("tfoot", children, out),
+        SpellDescriptionStruct::Row(children) => tag("tr", children, out),
+        SpellDescriptionStruct::Header(children) => tag("th", children, out),
+        SpellDescriptionStruct::Cell(children) => tag("td", children, out),
+        SpellDescriptionStruct::Italics(text) => out.push_str(&format!("<i>{}</i>", escape(text))),
+        SpellDescriptionStruct::Bold(text) => out.push_str(&format!("<b>{}</b>", escape(text))),
+        SpellDescriptionStruct::Text(text) => out.push_str(&escape(text)),
+        SpellDescriptionStruct::Br => out.push_str("<br>"),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
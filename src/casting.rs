@@ -0,0 +1,449 @@
+//! Spells per day of the spellcasting classes, see the class tables of the core rules.
+
+use crate::spell::ClassType;
+
+/// Spells per day from 1st spell level up, by class level (1-20).
+type Progression = [&'static [u32]; 20];
+
+/// Wizard, Cleric, Druid, Witch and Shaman.
+const FULL_PREPARED: Progression = [
+    &[1],
+    &[2],
+    &[2, 1],
+    &[3, 2],
+    &[3, 2, 1],
+    &[3, 3, 2],
+    &[4, 3, 2, 1],
+    &[4, 3, 3, 2],
+    &[4, 4, 3, 2, 1],
+    &[4, 4, 3, 3, 2],
+    &[4, 4, 4, 3, 2, 1],
+    &[4, 4, 4, 3, 3, 2],
+    &[4, 4, 4, 4, 3, 2, 1],
+    &[4, 4, 4, 4, 3, 3, 2],
+    &[4, 4, 4, 4, 4, 3, 2, 1],
+    &[4, 4, 4, 4, 4, 3, 3, 2],
+    &[4, 4, 4, 4, 4, 4, 3, 2, 1],
+    &[4, 4, 4, 4, 4, 4, 3, 3, 2],
+    &[4, 4, 4, 4, 4, 4, 4, 3, 3],
+    &[4, 4, 4, 4, 4, 4, 4, 4, 4],
+];
+
+/// Sorcerer, Oracle and Psychic.
+const FULL_SPONTANEOUS: Progression = [
+    &[3],
+    &[4],
+    &[5],
+    &[6, 3],
+    &[6, 4],
+    &[6, 5, 3],
+    &[6, 6, 4],
+    &[6, 6, 5, 3],
+    &[6, 6, 6, 4],
+    &[6, 6, 6, 5, 3],
+    &[6, 6, 6, 6, 4],
+    &[6, 6, 6, 6, 5, 3],
+    &[6, 6, 6, 6, 6, 4],
+    &[6, 6, 6, 6, 6, 5, 3],
+    &[6, 6, 6, 6, 6, 6, 4],
+    &[6, 6, 6, 6, 6, 6, 5, 3],
+    &[6, 6, 6, 6, 6, 6, 6, 4],
+    &[6, 6, 6, 6, 6, 6, 6, 5, 3],
+    &[6, 6, 6, 6, 6, 6, 6, 6, 4],
+    &[6, 6, 6, 6, 6, 6, 6, 6, 6],
+];
+
+/// Arcanist, a full caster that gains each spell level one class level later.
+const ARCANIST: Progression = [
+    &[2],
+    &[3],
+    &[4],
+    &[4, 2],
+    &[4, 3],
+    &[4, 4, 2],
+    &[4, 4, 3],
+    &[4, 4, 4, 2],
+    &[4, 4, 4, 3],
+    &[4, 4, 4, 4, 2],
+    &[4, 4, 4, 4, 3],
+    &[4, 4, 4, 4, 4, 2],
+    &[4, 4, 4, 4, 4, 3],
+    &[4, 4, 4, 4, 4, 4, 2],
+    &[4, 4, 4, 4, 4, 4, 3],
+    &[4, 4, 4, 4, 4, 4, 4, 2],
+    &[4, 4, 4, 4, 4, 4, 4, 3],
+    &[4, 4, 4, 4, 4, 4, 4, 4, 2],
+    &[4, 4, 4, 4, 4, 4, 4, 4, 3],
+    &[4, 4, 4, 4, 4, 4, 4, 4, 4],
+];
+
+/// The 6th level casters, like Bard, Magus or Alchemist.
+const SIX_LEVELS: Progression = [
+    &[1],
+    &[2],
+    &[3],
+    &[3, 1],
+    &[4, 2],
+    &[4, 3],
+    &[4, 3, 1],
+    &[4, 4, 2],
+    &[5, 4, 3],
+    &[5, 4, 3, 1],
+    &[5, 4, 4, 2],
+    &[5, 5, 4, 3],
+    &[5, 5, 4, 3, 1],
+    &[5, 5, 4, 4, 2],
+    &[5, 5, 5, 4, 3],
+    &[5, 5, 5, 4, 3, 1],
+    &[5, 5, 5, 4, 4, 2],
+    &[5, 5, 5, 5, 4, 3],
+    &[5, 5, 5, 5, 5, 4],
+    &[5, 5, 5, 5, 5, 5],
+];
+
+/// The 4th level casters, like Paladin or Ranger, a bonus spell is needed to cast a spell
+/// level in the first levels that have it.
+const FOUR_LEVELS: Progression = [
+    &[],
+    &[],
+    &[],
+    &[0],
+    &[1],
+    &[1],
+    &[1, 0],
+    &[1, 1],
+    &[2, 1],
+    &[2, 1, 0],
+    &[2, 1, 1],
+    &[2, 2, 1],
+    &[3, 2, 1, 0],
+    &[3, 2, 1, 1],
+    &[3, 2, 2, 1],
+    &[3, 3, 2, 1],
+    &[4, 3, 2, 1],
+    &[4, 3, 2, 2],
+    &[4, 3, 3, 2],
+    &[4, 4, 3, 3],
+];
+
+/// Bloodrager, unlike [`FOUR_LEVELS`] a new spell level starts with a slot.
+const BLOODRAGER: Progression = [
+    &[],
+    &[],
+    &[],
+    &[1],
+    &[1],
+    &[1],
+    &[1, 1],
+    &[1, 1],
+    &[2, 1],
+    &[2, 1, 1],
+    &[2, 1, 1],
+    &[2, 2, 1],
+    &[3, 2, 1, 1],
+    &[3, 2, 1, 1],
+    &[3, 2, 2, 1],
+    &[3, 3, 2, 1],
+    &[4, 3, 2, 1],
+    &[4, 3, 2, 2],
+    &[4, 3, 3, 2],
+    &[4, 4, 3, 3],
+];
+
+const ADEPT: Progression = [
+    &[1],
+    &[1],
+    &[2],
+    &[2, 0],
+    &[2, 1],
+    &[2, 1],
+    &[3, 2],
+    &[3, 2, 0],
+    &[3, 2, 1],
+    &[3, 2, 1],
+    &[3, 3, 2],
+    &[3, 3, 2, 0],
+    &[3, 3, 2, 1],
+    &[3, 3, 2, 1],
+    &[3, 3, 3, 2],
+    &[3, 3, 3, 2, 0],
+    &[3, 3, 3, 2, 1],
+    &[3, 3, 3, 2, 1],
+    &[3, 3, 3, 3, 2],
+    &[3, 3, 3, 3, 2],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Casting {
+    /// Spells are prepared into slots ahead of time.
+    Prepared,
+    /// Any known spell can be cast into a free slot.
+    Spontaneous,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cantrips {
+    None,
+    /// Prepared into their own slots, 3 at 1st level and 4 afterwards.
+    Prepared,
+    /// Prepared into their own slots, 3 at every level like the orisons of an Adept.
+    PreparedThree,
+    AtWill,
+}
+
+struct ClassCasting {
+    casting: Casting,
+    progression: &'static Progression,
+    cantrips: Cantrips,
+    ability: &'static str,
+}
+
+fn class_casting(class: &ClassType) -> ClassCasting {
+    use Cantrips as C;
+    use Casting::*;
+    let (casting, progression, cantrips, ability) = match class {
+        ClassType::Wizard(_) => (Prepared, &FULL_PREPARED, C::Prepared, "Intelligence"),
+        ClassType::Witch(_) => (Prepared, &FULL_PREPARED, C::Prepared, "Intelligence"),
+        ClassType::Cleric(_) => (Prepared, &FULL_PREPARED, C::Prepared, "Wisdom"),
+        ClassType::Druid(_) => (Prepared, &FULL_PREPARED, C::Prepared, "Wisdom"),
+        ClassType::Shaman(_) => (Prepared, &FULL_PREPARED, C::Prepared, "Wisdom"),
+        ClassType::Sorcerer(_) => (Spontaneous, &FULL_SPONTANEOUS, C::AtWill, "Charisma"),
+        ClassType::Oracle(_) => (Spontaneous, &FULL_SPONTANEOUS, C::AtWill, "Charisma"),
+        ClassType::Psychic(_) => (Spontaneous, &FULL_SPONTANEOUS, C::AtWill, "Intelligence"),
+        // casts any of the spells prepared for the day into a free slot
+        ClassType::Arcanist(_) => (Spontaneous, &ARCANIST, C::AtWill, "Intelligence"),
+        ClassType::Bard(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Charisma"),
+        ClassType::Summoner(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Charisma"),
+        ClassType::UncSummoner(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Charisma"),
+        ClassType::Skald(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Charisma"),
+        ClassType::Mesmerist(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Charisma"),
+        ClassType::Inquisitor(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Wisdom"),
+        ClassType::Warpriest(_) => (Prepared, &SIX_LEVELS, C::Prepared, "Wisdom"),
+        ClassType::Hunter(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Wisdom"),
+        ClassType::Spiritualist(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Wisdom"),
+        ClassType::Occultist(_) => (Spontaneous, &SIX_LEVELS, C::AtWill, "Intelligence"),
+        ClassType::Magus(_) => (Prepared, &SIX_LEVELS, C::Prepared, "Intelligence"),
+        ClassType::Alchemist(_) => (Prepared, &SIX_LEVELS, C::None, "Intelligence"),
+        ClassType::Investigator(_) => (Prepared, &SIX_LEVELS, C::None, "Intelligence"),
+        ClassType::Paladin(_) => (Prepared, &FOUR_LEVELS, C::None, "Charisma"),
+        ClassType::Antipaladin(_) => (Prepared, &FOUR_LEVELS, C::None, "Charisma"),
+        ClassType::Ranger(_) => (Prepared, &FOUR_LEVELS, C::None, "Wisdom"),
+        ClassType::Bloodrager(_) => (Spontaneous, &BLOODRAGER, C::None, "Charisma"),
+        ClassType::Medium(_) => (Spontaneous, &FOUR_LEVELS, C::AtWill, "Charisma"),
+        ClassType::Adept(_) => (Prepared, &ADEPT, C::PreparedThree, "Wisdom"),
+    };
+    ClassCasting {
+        casting,
+        progression,
+        cantrips,
+        ability,
+    }
+}
+
+pub fn casting(class: &ClassType) -> Casting {
+    class_casting(class).casting
+}
+
+/// Name of the ability score that determines bonus spells.
+pub fn casting_ability(class: &ClassType) -> &'static str {
+    class_casting(class).ability
+}
+
+/// Spells per day of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpellSlots {
+    /// Slots by spell level including bonus spells, `None` if the level can't be cast.
+    pub per_day: [Option<u32>; 10],
+    /// 0-level spells can be cast without using up slots.
+    pub at_will_cantrips: bool,
+}
+
+impl SpellSlots {
+    /// The slots of `class` at `caster_level` (1-20) with the given casting ability score.
+    pub fn new(class: &ClassType, caster_level: u32, ability_score: u32) -> Self {
+        let class_casting = class_casting(class);
+        let level = caster_level.clamp(1, 20);
+        let mut per_day = [None; 10];
+
+        per_day[0] = match class_casting.cantrips {
+            Cantrips::Prepared if level == 1 => Some(3),
+            Cantrips::Prepared => Some(4),
+            Cantrips::PreparedThree => Some(3),
+            _ => None,
+        };
+        let row = class_casting.progression[level as usize - 1];
+        for (i, base) in row.iter().enumerate() {
+            let spell_level = i as u32 + 1;
+            per_day[i + 1] = Some(base + bonus_spells(ability_score, spell_level));
+        }
+        // casting a spell requires a score of at least 10 + its level
+        for (spell_level, slots) in per_day.iter_mut().enumerate() {
+            if ability_score < 10 + spell_level as u32 {
+                *slots = None;
+            }
+        }
+
+        Self {
+            per_day,
+            at_will_cantrips: class_casting.cantrips == Cantrips::AtWill,
+        }
+    }
+}
+
+/// Bonus spells per day of a spell level from a high ability score.
+pub fn bonus_spells(ability_score: u32, spell_level: u32) -> u32 {
+    let modifier = (ability_score as i64 - 10).div_euclid(2);
+    if spell_level == 0 || modifier < spell_level as i64 {
+        0
+    } else {
+        1 + (modifier - spell_level as i64) as u32 / 4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use filter_repr::FilterState;
+
+    use super::*;
+
+    const N: FilterState = FilterState::None;
+
+    #[test]
+    fn progressions() {
+        for (name, progression) in [
+            ("full prepared", &FULL_PREPARED),
+            ("full spontaneous", &FULL_SPONTANEOUS),
+            ("arcanist", &ARCANIST),
+            ("six levels", &SIX_LEVELS),
+            ("four levels", &FOUR_LEVELS),
+            ("bloodrager", &BLOODRAGER),
+            ("adept", &ADEPT),
+        ] {
+            for (i, pair) in progression.windows(2).enumerate() {
+                let (prev, next) = (pair[0], pair[1]);
+                assert!(next.len() <= 9, "{name} {}: too many levels", i + 2);
+                assert!(next.len() >= prev.len(), "{name} {}: lost a level", i + 2);
+                for (level, (a, b)) in prev.iter().zip(next.iter()).enumerate() {
+                    assert!(a <= b, "{name} {}: fewer level {} slots", i + 2, level + 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn progression_rows() {
+        for (progression, class_level, row) in [
+            (&FULL_PREPARED, 1, &[1u32][..]),
+            (&FULL_PREPARED, 20, &[4; 9][..]),
+            (&FULL_SPONTANEOUS, 4, &[6, 3][..]),
+            (&FULL_SPONTANEOUS, 20, &[6; 9][..]),
+            (&ARCANIST, 20, &[4; 9][..]),
+            (&SIX_LEVELS, 7, &[4, 3, 1][..]),
+            (&SIX_LEVELS, 20, &[5; 6][..]),
+            (&FOUR_LEVELS, 3, &[][..]),
+            (&FOUR_LEVELS, 4, &[0][..]),
+            (&FOUR_LEVELS, 13, &[3, 2, 1, 0][..]),
+            (&BLOODRAGER, 4, &[1][..]),
+            (&BLOODRAGER, 7, &[1, 1][..]),
+            (&BLOODRAGER, 10, &[2, 1, 1][..]),
+            (&BLOODRAGER, 13, &[3, 2, 1, 1][..]),
+            (&ADEPT, 20, &[3, 3, 3, 3, 2][..]),
+        ] {
+            assert_eq!(
+                progression[class_level - 1],
+                row,
+                "class level {class_level}"
+            );
+        }
+    }
+
+    #[test]
+    fn bonus_spells_by_score() {
+        for (score, spell_level, bonus) in [
+            (30, 0, 0),
+            (8, 1, 0),
+            (11, 1, 0),
+            (12, 1, 1),
+            (13, 1, 1),
+            (12, 2, 0),
+            (14, 2, 1),
+            (19, 4, 1),
+            (19, 5, 0),
+            (20, 1, 2),
+            (20, 5, 1),
+            (28, 1, 3),
+            (28, 9, 1),
+        ] {
+            assert_eq!(
+                bonus_spells(score, spell_level),
+                bonus,
+                "score {score}, level {spell_level}"
+            );
+        }
+    }
+
+    #[test]
+    fn slots() {
+        for (class, caster_level, score, per_day, at_will) in [
+            (
+                ClassType::Wizard(N),
+                20,
+                19,
+                vec![
+                    Some(4),
+                    Some(5),
+                    Some(5),
+                    Some(5),
+                    Some(5),
+                    Some(4),
+                    Some(4),
+                    Some(4),
+                    Some(4),
+                    Some(4),
+                ],
+                false,
+            ),
+            (ClassType::Wizard(N), 2, 11, vec![Some(4), Some(2)], false),
+            (
+                ClassType::Sorcerer(N),
+                4,
+                12,
+                vec![None, Some(7), Some(3)],
+                true,
+            ),
+            (ClassType::Paladin(N), 4, 12, vec![None, Some(1)], false),
+            (ClassType::Paladin(N), 3, 18, vec![], false),
+            (ClassType::Bloodrager(N), 4, 11, vec![None, Some(1)], false),
+            (
+                ClassType::Adept(N),
+                5,
+                12,
+                vec![Some(3), Some(3), Some(1)],
+                false,
+            ),
+        ] {
+            let mut expected: [Option<u32>; 10] = [None; 10];
+            expected[..per_day.len()].copy_from_slice(&per_day);
+            let slots = SpellSlots::new(&class, caster_level, score);
+            assert_eq!(slots.per_day, expected, "{class:?} {caster_level}");
+            assert_eq!(slots.at_will_cantrips, at_will, "{class:?} {caster_level}");
+        }
+    }
+
+    #[test]
+    fn ability_cutoff() {
+        // a spell level needs a score of at least 10 + the level
+        let wizard = ClassType::Wizard(N);
+        for (score, highest) in [
+            (9, None),
+            (10, Some(0)),
+            (11, Some(1)),
+            (15, Some(5)),
+            (19, Some(9)),
+        ] {
+            let slots = SpellSlots::new(&wizard, 20, score);
+            let castable = slots.per_day.iter().rposition(Option::is_some);
+            assert_eq!(castable, highest, "score {score}");
+        }
+    }
+}
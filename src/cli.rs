@@ -0,0 +1,183 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+#[cfg(not(target_arch = "wasm32"))]
+use pf1_spell_search::SpellQuery;
+
+// The command line frontend only makes sense natively.
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+const USAGE: &str = "Usage: pf1-spells [OPTIONS] [QUERY]...
+
+Search the PF1 spell database from the terminal.
+QUERY uses the same syntax as the search bar of the app, e.g.
+    pf1-spells class:wiz level:<=3 desc:fire -descriptor:evil
+Words without a key search the spell names.
+Prefix a filter value with '!' to exclude it instead, e.g. --descriptor !evil.
+Filter values may be abbreviated as long as they are unambiguous, e.g. --class wiz.
+
+Options:
+  -c, --class <CLASS>            Class that has the spell on its list
+  -l, --level <LEVEL>            Spell level (0-9, <=3, 2-4, wizard<=3, ...)
+  -s, --school <SCHOOL>          Spell school
+      --subschool <SUBSCHOOL>    Spell subschool
+  -d, --descriptor <DESCRIPTOR>  Spell descriptor
+      --domain <DOMAIN>          Cleric domain
+      --component <COMPONENT>    Verbal, Somatic, Material, Focus, Divine Focus,
+                                 Costly Material, Thought or Emotion
+      --cost <GP>                Maximum material component cost, e.g. '<=500'
+      --casting <CASTING>        Casting time (Swift, Standard, Full-Round, Minutes, ...)
+      --range <RANGE>            Personal, Touch, Close, Medium, Long, Fixed, Unlimited
+                                 or a minimum like '>=100ft@cl5'
+      --duration <DURATION>      Kind of duration (Instantaneous, Per Level, ...)
+                                 or a minimum like '>=10min/level'
+      --area <AREA>              Shape of the area or effect (Cone, Burst, ...)
+                                 or a minimum radius or length like '>=20ft'
+      --targets <TARGETS>        You, One Target, Multiple Targets or See Text
+      --save <SAVE>              Saving throw
+      --sr <SR>                  Spell resistance (Yes, No or Text)
+      --flag <FLAG>              Dismissible, Shapeable, Mythic or Augmented
+  -S, --source <SOURCE>          Source book
+  -k, --keywords <KEYWORDS>      Comma separated keywords to search the description for
+                                 ('-word' excludes, 'a|b' either, '/regex/')
+  -f, --search <TEXT>            Full text search, ranks the spells with the best matches first
+      --or <FILTER>              Combine the values of FILTER (e.g. class, level) with OR
+  -h, --help                     Print this help";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return;
+    }
+
+    match parse_args(&args) {
+        Ok(query) => {
+            let mut spells = query.run();
+            if spells.is_empty() {
+                let suggestions = query.suggestions(5);
+                if !suggestions.is_empty() {
+                    eprintln!("no spells found, did you mean: {}", suggestions.join(", "));
+                }
+            }
+            if query.has_search() {
+                spells.sort_by(|(a, _), (b, _)| {
+                    let (a, b) = (query.relevance(a), query.relevance(b));
+                    b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+                });
+            }
+            for (spell, level) in spells {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    spell.name, level, spell.school, spell.short_description
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_args(args: &[String]) -> Result<SpellQuery, String> {
+    let mut terms = Vec::new();
+    let mut or_rows = Vec::new();
+    let mut searches: Vec<&str> = Vec::new();
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        if !arg.starts_with("--") && !(arg.len() == 2 && arg.starts_with('-')) {
+            terms.push(arg.clone());
+            continue;
+        }
+        let value = it
+            .next()
+            .ok_or_else(|| format!("missing value for '{arg}'"))?;
+        let key = match arg.as_str() {
+            "-c" | "--class" => "class",
+            "-l" | "--level" => "level",
+            "-s" | "--school" => "school",
+            "--subschool" => "subschool",
+            "-d" | "--descriptor" => "descriptor",
+            "--domain" => "domain",
+            "--component" => "component",
+            "--cost" => "cost",
+            "--casting" => "casting",
+            "--range" => "range",
+            "--duration" => "duration",
+            "--area" => "area",
+            "--targets" => "targets",
+            "--save" => "save",
+            "--sr" => "sr",
+            "--flag" => "flag",
+            "-S" | "--source" => "source",
+            "-k" | "--keywords" => "desc",
+            "--or" => {
+                or_rows.push(value.as_str());
+                continue;
+            }
+            // the text keeps its own syntax instead of becoming a single term
+            "-f" | "--search" => {
+                searches.push(value.as_str());
+                continue;
+            }
+            other => return Err(format!("unknown option '{other}'")),
+        };
+        terms.push(term(key, value));
+    }
+
+    let text = terms.join(" ");
+    let mut query = SpellQuery::parse(&text).map_err(|e| {
+        format!(
+            "{}\n  {text}\n  {}{}",
+            e.message,
+            " ".repeat(text[..e.span.start].chars().count()),
+            "^".repeat(text[e.span.clone()].chars().count().max(1)),
+        )
+    })?;
+    for row in or_rows {
+        match row {
+            "class" => query.class_or = true,
+            "level" => query.level_or = true,
+            "school" => query.school_or = true,
+            "subschool" => query.subschool_or = true,
+            "descriptor" => query.descriptor_or = true,
+            "domain" => query.domain_or = true,
+            "component" => query.components_or = true,
+            "casting" => query.casting_time_or = true,
+            "range" => query.range_or = true,
+            "duration" => query.duration_or = true,
+            "area" => query.area_or = true,
+            "targets" => query.targets_or = true,
+            "save" => query.save_or = true,
+            "sr" => query.spell_res_or = true,
+            "flag" => query.flags_or = true,
+            "source" => query.source_or = true,
+            other => return Err(format!("unknown filter '{other}' for --or")),
+        }
+    }
+    if !searches.is_empty() {
+        let search = format!("{} {}", query.search, searches.join(" "));
+        query.search = search.trim().to_string();
+    }
+    query.update_keywords();
+
+    Ok(query)
+}
+
+/// Turns an option value into a query term, a leading '!' excludes the value.
+#[cfg(not(target_arch = "wasm32"))]
+fn term(key: &str, value: &str) -> String {
+    let (sign, value) = match value.strip_prefix('!') {
+        Some(v) => ("-", v),
+        None => ("", value),
+    };
+    format!(
+        "{sign}{key}:\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
@@ -0,0 +1,153 @@
+/// File formats the shown spells can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn get_all() -> [Self; 3] {
+        [Self::Csv, Self::Json, Self::Markdown]
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+            Self::Markdown => "Markdown",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Markdown => "md",
+        }
+    }
+
+    /// Formats a table, every row has one value per header.
+    pub fn render(&self, headers: &[String], rows: &[Vec<&str>]) -> String {
+        match self {
+            Self::Csv => {
+                let mut out = csv_line(headers.iter().map(String::as_str));
+                for row in rows {
+                    out += &csv_line(row.iter().copied());
+                }
+                out
+            }
+            Self::Json => {
+                // built by hand to keep the column order
+                let objects: Vec<String> = rows
+                    .iter()
+                    .map(|row| {
+                        let fields: Vec<String> = headers
+                            .iter()
+                            .zip(row)
+                            .map(|(h, v)| format!("    {}: {}", json_string(h), json_string(v)))
+                            .collect();
+                        format!("  {{\n{}\n  }}", fields.join(",\n"))
+                    })
+                    .collect();
+                format!("[\n{}\n]\n", objects.join(",\n"))
+            }
+            Self::Markdown => {
+                let line = |values: Vec<String>| format!("| {} |\n", values.join(" | "));
+                let mut out = line(headers.iter().map(|h| markdown_cell(h)).collect());
+                out += &line(headers.iter().map(|_| "---".to_string()).collect());
+                for row in rows {
+                    out += &line(row.iter().map(|v| markdown_cell(v)).collect());
+                }
+                out
+            }
+        }
+    }
+
+    pub fn save(&self, content: &str) {
+        save(&format!("spells.{}", self.extension()), content);
+    }
+}
+
+/// Asks where to save `content`, proposing `file_name`, and writes it there.
+#[cfg(all(not(target_arch = "wasm32"), feature = "file_dialog"))]
+pub fn save(file_name: &str, content: &str) {
+    let mut dialog = rfd::FileDialog::new().set_file_name(file_name);
+    if let Some((_, extension)) = file_name.rsplit_once('.') {
+        dialog = dialog.add_filter(extension.to_uppercase(), &[extension]);
+    }
+    let Some(path) = dialog.save_file() else {
+        return;
+    };
+    if let Err(e) = std::fs::write(&path, content) {
+        log::error!("could not write {}: {e}", path.display());
+    }
+}
+
+/// Writes `content` to `file_name` in the working directory, without the `file_dialog`
+/// feature there is no dialog to ask for a place.
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "file_dialog")))]
+pub fn save(file_name: &str, content: &str) {
+    match std::fs::write(file_name, content) {
+        Ok(()) => log::info!("saved {file_name}"),
+        Err(e) => log::error!("could not write {file_name}: {e}"),
+    }
+}
+
+/// Lets the browser download `content` as `file_name`.
+#[cfg(target_arch = "wasm32")]
+pub fn save(file_name: &str, content: &str) {
+    use wasm_bindgen::JsCast;
+
+    let mime = match file_name.rsplit_once('.').map(|(_, e)| e) {
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("md") => "text/markdown",
+        Some("html") => "text/html",
+        _ => "text/plain",
+    };
+    let download = || -> Result<(), wasm_bindgen::JsValue> {
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or("no document found")?;
+        let anchor: web_sys::HtmlElement = document.create_element("a")?.dyn_into()?;
+        let encoded: String = content
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (b as char).to_string(),
+                b => format!("%{b:02X}"),
+            })
+            .collect();
+        anchor.set_attribute("href", &format!("data:{mime};charset=utf-8,{encoded}"))?;
+        anchor.set_attribute("download", file_name)?;
+        anchor.click();
+        Ok(())
+    };
+    if let Err(e) = download() {
+        log::error!("could not download {file_name}: {e:?}");
+    }
+}
+
+fn csv_line<'a>(values: impl Iterator<Item = &'a str>) -> String {
+    values.map(csv_field).collect::<Vec<_>>().join(",") + "\r\n"
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
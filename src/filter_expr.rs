@@ -0,0 +1,366 @@
+use filter_repr::{FilterRepr, FilterState};
+
+use crate::{
+    filters::{
+        AreaShape, CastingTimeType, Domain, DurationType, Level, Save, SpellComponent,
+        SpellDescriptor, SpellFlag, SpellRange, SpellResistance, SpellSource, Spellschool,
+        Subschool, TargetType,
+    },
+    fuzzy,
+    query::{MinArea, MinDuration, MinRange},
+    spell::{ClassType, Spell},
+    util::{min_area_ui, min_duration_ui, min_range_ui},
+};
+
+/// A boolean combination of spell predicates, e.g. "(Fire OR Cold) AND NOT Evil".
+///
+/// An empty AND matches everything, so a freshly added group doesn't empty the table,
+/// an empty OR has no alternative that could match and matches nothing.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum FilterExpr {
+    And(Vec<FilterExpr>),
+    Or(Vec<FilterExpr>),
+    Not(Box<FilterExpr>),
+    Leaf(Predicate),
+}
+
+impl Default for FilterExpr {
+    fn default() -> Self {
+        Self::And(Vec::new())
+    }
+}
+
+impl FilterExpr {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::And(children) | Self::Or(children) => children.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Tests the expression, `level` is the level string from [`Spell::level_string`].
+    pub fn test(&self, spell: &Spell, level: &str) -> bool {
+        match self {
+            Self::And(children) => children.iter().all(|e| e.test(spell, level)),
+            Self::Or(children) => children.iter().any(|e| e.test(spell, level)),
+            Self::Not(inner) => !inner.test(spell, level),
+            Self::Leaf(predicate) => predicate.test(spell, level),
+        }
+    }
+
+    /// Shows an editor for the expression, returns whether it was changed.
+    pub fn editor_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        self.node_ui(ui, ui.id().with("filter_expr"), false, &mut changed);
+        changed
+    }
+
+    /// Returns whether the node should be removed from its parent.
+    fn node_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        removable: bool,
+        changed: &mut bool,
+    ) -> bool {
+        let negated = matches!(self, Self::Not(_));
+        let mut toggle_not = false;
+        let mut switch_group = false;
+        let mut remove = false;
+
+        let inner = match &mut *self {
+            Self::Not(inner) => inner.as_mut(),
+            other => other,
+        };
+        let is_or = matches!(inner, Self::Or(_));
+        match inner {
+            Self::And(children) | Self::Or(children) => {
+                ui.horizontal(|ui| {
+                    toggle_not = ui.selectable_label(negated, "NOT").clicked();
+                    egui::ComboBox::from_id_salt(id.with("group"))
+                        .selected_text(if is_or { "any of" } else { "all of" })
+                        .show_ui(ui, |ui| {
+                            switch_group |=
+                                ui.selectable_label(!is_or, "all of").clicked() && is_or;
+                            switch_group |=
+                                ui.selectable_label(is_or, "any of").clicked() && !is_or;
+                        });
+                    if ui.button("+ Condition").clicked() {
+                        children.push(Self::Leaf(Predicate::default()));
+                        *changed = true;
+                    }
+                    if ui.button("+ Group").clicked() {
+                        children.push(Self::default());
+                        *changed = true;
+                    }
+                    remove = removable && ui.button("🗑").clicked();
+                });
+                ui.indent(id, |ui| {
+                    let mut removed = None;
+                    for (i, child) in children.iter_mut().enumerate() {
+                        if child.node_ui(ui, id.with(i), true, changed) {
+                            removed = Some(i);
+                        }
+                    }
+                    if let Some(i) = removed {
+                        children.remove(i);
+                        *changed = true;
+                    }
+                });
+            }
+            Self::Leaf(predicate) => {
+                ui.horizontal(|ui| {
+                    toggle_not = ui.selectable_label(negated, "NOT").clicked();
+                    *changed |= predicate.ui(ui, id);
+                    remove = removable && ui.button("🗑").clicked();
+                });
+            }
+            Self::Not(_) => {
+                ui.horizontal(|ui| {
+                    toggle_not = ui.selectable_label(negated, "NOT").clicked();
+                    remove = removable && ui.button("🗑").clicked();
+                });
+                ui.indent(id, |ui| {
+                    inner.node_ui(ui, id.with("not"), false, changed);
+                });
+            }
+        }
+
+        if switch_group {
+            let inner = match &mut *self {
+                Self::Not(inner) => inner.as_mut(),
+                other => other,
+            };
+            *inner = match std::mem::take(inner) {
+                Self::And(children) => Self::Or(children),
+                Self::Or(children) => Self::And(children),
+                other => other,
+            };
+            *changed = true;
+        }
+        if toggle_not {
+            *self = match std::mem::take(self) {
+                Self::Not(inner) => *inner,
+                other => Self::Not(Box::new(other)),
+            };
+            *changed = true;
+        }
+        remove
+    }
+}
+
+/// A single condition of a [`FilterExpr`], the contained filters are always `Positive`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Predicate {
+    Name(String),
+    Class(ClassType),
+    ClassLevel {
+        class: ClassType,
+        min: u32,
+        max: u32,
+    },
+    /// A spell list whose name contains the text, like "fire domain", "draconic bloodline"
+    /// or "flame mystery".
+    List(String),
+    Level(Level),
+    School(Spellschool),
+    Subschool(Subschool),
+    Domain(Domain),
+    Descriptor(SpellDescriptor),
+    Component(SpellComponent),
+    /// Material components costing at most this many gp.
+    MaxCost(u32),
+    CastingTime(CastingTimeType),
+    Range(SpellRange),
+    MinRange(MinRange),
+    Duration(DurationType),
+    MinDuration(MinDuration),
+    Area(AreaShape),
+    MinArea(MinArea),
+    Targets(TargetType),
+    Save(Save),
+    SpellResistance(SpellResistance),
+    Flag(SpellFlag),
+    Source(SpellSource),
+}
+
+impl Default for Predicate {
+    fn default() -> Self {
+        Self::School(first())
+    }
+}
+
+impl Predicate {
+    pub fn test(&self, spell: &Spell, level: &str) -> bool {
+        match self {
+            Self::Name(name) => fuzzy::contains(&spell.name, name),
+            Self::Class(f) => f.test_cls(spell),
+            Self::ClassLevel { class, min, max } => class
+                .get_value(spell)
+                .is_some_and(|l| *min <= l && l <= *max),
+            Self::List(name) => {
+                let name = name.to_lowercase();
+                spell
+                    .spell_levels
+                    .iter()
+                    .any(|(list, _)| list.to_string().to_lowercase().contains(&name))
+            }
+            Self::Level(f) => f.test(level),
+            Self::School(f) => f.test(&spell.school),
+            Self::Subschool(f) => f.test(&spell.subschool),
+            Self::Domain(f) => f.test(&spell.domain),
+            Self::Descriptor(f) => f.test(&spell.descriptors),
+            Self::Component(f) => f.special_test(spell),
+            Self::MaxCost(max) => spell.material_cost().is_some_and(|c| c <= *max),
+            Self::CastingTime(f) => f.special_test(spell),
+            Self::Range(f) => f.special_test(spell),
+            Self::MinRange(range) => range.test(spell),
+            Self::Duration(f) => f.special_test(spell),
+            Self::MinDuration(duration) => duration.test(spell),
+            Self::Area(f) => f.special_test(spell),
+            Self::MinArea(area) => area.test(spell),
+            Self::Targets(f) => f.special_test(spell),
+            Self::Save(f) => f.test(&spell.saving_throw),
+            Self::SpellResistance(f) => f.test(&spell.spell_resistance),
+            Self::Flag(f) => f.special_test(spell),
+            Self::Source(f) => f.test_exact(&spell.source),
+        }
+    }
+
+    /// One predicate of every kind, used to switch between kinds in the editor.
+    fn kinds() -> Vec<Self> {
+        [
+            Self::Name(String::new()),
+            Self::Class(first()),
+            Self::ClassLevel {
+                class: first(),
+                min: 0,
+                max: 9,
+            },
+            Self::List(String::new()),
+            Self::Level(first()),
+            Self::School(first()),
+            Self::Subschool(first()),
+            Self::Domain(first()),
+            Self::Descriptor(first()),
+            Self::Component(first()),
+            Self::MaxCost(0),
+            Self::CastingTime(first()),
+            Self::Range(first()),
+            Self::MinRange(MinRange::default()),
+            Self::Duration(first()),
+            Self::MinDuration(MinDuration::default()),
+            Self::Area(first()),
+            Self::MinArea(MinArea::default()),
+            Self::Targets(first()),
+            Self::Save(first()),
+            Self::SpellResistance(first()),
+            Self::Flag(first()),
+            Self::Source(first()),
+        ]
+        .into()
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Name(_) => "Name",
+            Self::Class(_) => "Class",
+            Self::ClassLevel { .. } => "Class Level",
+            Self::List(_) => "Spell List",
+            Self::Level(_) => "Spell Level",
+            Self::School(_) => "Spellschool",
+            Self::Subschool(_) => "Subschool",
+            Self::Domain(_) => "Domain",
+            Self::Descriptor(_) => "Descriptor",
+            Self::Component(_) => "Component",
+            Self::MaxCost(_) => "Material Cost at most",
+            Self::CastingTime(_) => "Casting Time",
+            Self::Range(_) => "Range",
+            Self::MinRange(_) => "Range at least",
+            Self::Duration(_) => "Duration",
+            Self::MinDuration(_) => "Duration at least",
+            Self::Area(_) => "Area",
+            Self::MinArea(_) => "Area at least",
+            Self::Targets(_) => "Targets",
+            Self::Save(_) => "Save",
+            Self::SpellResistance(_) => "Spell Resistance",
+            Self::Flag(_) => "Flag",
+            Self::Source(_) => "Source",
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, id: egui::Id) -> bool {
+        let mut changed = false;
+        let kind_name = self.kind_name();
+        egui::ComboBox::from_id_salt(id.with("kind"))
+            .selected_text(kind_name)
+            .show_ui(ui, |ui| {
+                for kind in Self::kinds() {
+                    let name = kind.kind_name();
+                    if ui.selectable_label(kind_name == name, name).clicked() && kind_name != name {
+                        *self = kind;
+                        changed = true;
+                    }
+                }
+            });
+
+        changed |= match self {
+            Self::Name(name) | Self::List(name) => ui
+                .add(egui::TextEdit::singleline(name).desired_width(120.0))
+                .changed(),
+            Self::Class(f) => value_combo(ui, id, f),
+            Self::ClassLevel { class, min, max } => {
+                let mut changed = value_combo(ui, id, class);
+                changed |= ui.add(egui::DragValue::new(min).range(0..=9)).changed();
+                *max = (*max).max(*min);
+                ui.label("to");
+                changed |= ui.add(egui::DragValue::new(max).range(*min..=9)).changed();
+                changed
+            }
+            Self::Level(f) => value_combo(ui, id, f),
+            Self::School(f) => value_combo(ui, id, f),
+            Self::Subschool(f) => value_combo(ui, id, f),
+            Self::Domain(f) => value_combo(ui, id, f),
+            Self::Descriptor(f) => value_combo(ui, id, f),
+            Self::Component(f) => value_combo(ui, id, f),
+            Self::MaxCost(max) => ui
+                .add(egui::DragValue::new(max).range(0..=100_000).suffix(" gp"))
+                .changed(),
+            Self::CastingTime(f) => value_combo(ui, id, f),
+            Self::Range(f) => value_combo(ui, id, f),
+            Self::MinRange(range) => min_range_ui(ui, range),
+            Self::Duration(f) => value_combo(ui, id, f),
+            Self::MinDuration(duration) => min_duration_ui(ui, id, duration),
+            Self::Area(f) => value_combo(ui, id, f),
+            Self::MinArea(area) => min_area_ui(ui, area),
+            Self::Targets(f) => value_combo(ui, id, f),
+            Self::Save(f) => value_combo(ui, id, f),
+            Self::SpellResistance(f) => value_combo(ui, id, f),
+            Self::Flag(f) => value_combo(ui, id, f),
+            Self::Source(f) => value_combo(ui, id, f),
+        };
+        changed
+    }
+}
+
+/// The first variant of a filter enum, set to `Positive`.
+fn first<T: FilterRepr>() -> T {
+    T::get_all().remove(0).with_state(FilterState::Positive)
+}
+
+fn value_combo<T: FilterRepr + PartialEq>(ui: &mut egui::Ui, id: egui::Id, value: &mut T) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id.with("value"))
+        .selected_text(value.name())
+        .height(300.0)
+        .show_ui(ui, |ui| {
+            for v in T::get_all() {
+                let v = v.with_state(FilterState::Positive);
+                if ui.selectable_label(*value == v, v.name()).clicked() && *value != v {
+                    *value = v;
+                    changed = true;
+                }
+            }
+        });
+    changed
+}
@@ -109,21 +109,52 @@ pub enum SpellComponent {
     Verbal(FilterState),
     Somatic(FilterState),
     Material(FilterState),
+    Focus(FilterState),
+    #[name = "Divine Focus"]
+    DivineFocus(FilterState),
+    /// A material component with a listed price.
+    #[name = "Costly Material"]
+    Costly(FilterState),
+    /// Psychic spells only.
+    Thought(FilterState),
+    /// Psychic spells only.
+    Emotion(FilterState),
 }
 
 impl SpellComponent {
     pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
-        match self {
-            Self::Verbal(FilterState::None) => true,
-            Self::Somatic(FilterState::None) => true,
-            Self::Material(FilterState::None) => true,
-            Self::Verbal(FilterState::Positive) => spell.verbal,
-            Self::Somatic(FilterState::Positive) => spell.somatic,
-            Self::Material(FilterState::Positive) => spell.material,
-            Self::Verbal(FilterState::Negative) => !spell.verbal,
-            Self::Somatic(FilterState::Negative) => !spell.somatic,
-            Self::Material(FilterState::Negative) => !spell.material,
-        }
+        let matches = match self {
+            Self::Verbal(_) => spell.verbal,
+            Self::Somatic(_) => spell.somatic,
+            Self::Material(_) => spell.material,
+            Self::Focus(_) => spell.focus,
+            Self::DivineFocus(_) => spell.divine_focus,
+            Self::Costly(_) => spell.costly_components,
+            Self::Thought(_) => spell.thought_component,
+            Self::Emotion(_) => spell.emotion_component,
+        };
+        self.state().test_match(matches)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, FilterReprMacro)]
+pub enum SpellFlag {
+    Dismissible(FilterState),
+    Shapeable(FilterState),
+    Mythic(FilterState),
+    /// Mythic spells with an augmented version.
+    Augmented(FilterState),
+}
+
+impl SpellFlag {
+    pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
+        let matches = match self {
+            Self::Dismissible(_) => spell.dismissible,
+            Self::Shapeable(_) => spell.shapeable,
+            Self::Mythic(_) => spell.mythic,
+            Self::Augmented(_) => !spell.augmented.is_empty(),
+        };
+        self.state().test_match(matches)
     }
 }
 
@@ -168,6 +199,181 @@ pub enum SpellRange {
     Close(FilterState),
     Medium(FilterState),
     Long(FilterState),
+    /// A distance in feet or miles, like "60 ft." or "1 mile/level".
+    Fixed(FilterState),
+    Unlimited(FilterState),
+    #[name = "See Text"]
+    Special(FilterState),
+}
+
+impl SpellRange {
+    pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
+        use crate::spell::RangeValue;
+
+        let matches = match self {
+            Self::Personal(_) => spell.range_value == RangeValue::Personal,
+            Self::Touch(_) => spell.range_value == RangeValue::Touch,
+            Self::Close(_) => spell.range_value == RangeValue::Close,
+            Self::Medium(_) => spell.range_value == RangeValue::Medium,
+            Self::Long(_) => spell.range_value == RangeValue::Long,
+            Self::Fixed(_) => matches!(spell.range_value, RangeValue::Fixed { .. }),
+            Self::Unlimited(_) => spell.range_value == RangeValue::Unlimited,
+            Self::Special(_) => spell.range_value == RangeValue::Special,
+        };
+        self.state().test_match(matches)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, FilterReprMacro)]
+pub enum DurationType {
+    Instantaneous(FilterState),
+    Permanent(FilterState),
+    Concentration(FilterState),
+    Rounds(FilterState),
+    Minutes(FilterState),
+    Hours(FilterState),
+    /// A day or longer.
+    Days(FilterState),
+    #[name = "Per Level"]
+    PerLevel(FilterState),
+    Dismissible(FilterState),
+    #[name = "Until Discharged"]
+    Discharge(FilterState),
+}
+
+impl DurationType {
+    pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
+        use crate::spell::{DurationKind, TimeUnit};
+
+        let duration = &spell.duration_value;
+        let unit = match duration.kind {
+            DurationKind::Timed { unit, .. } => Some(unit),
+            _ => None,
+        };
+        let matches = match self {
+            Self::Instantaneous(_) => duration.kind == DurationKind::Instantaneous,
+            Self::Permanent(_) => duration.kind == DurationKind::Permanent,
+            Self::Concentration(_) => duration.concentration,
+            Self::Rounds(_) => unit == Some(TimeUnit::Round),
+            Self::Minutes(_) => unit == Some(TimeUnit::Minute),
+            Self::Hours(_) => unit == Some(TimeUnit::Hour),
+            Self::Days(_) => unit.is_some_and(|u| u >= TimeUnit::Day),
+            Self::PerLevel(_) => matches!(
+                duration.kind,
+                DurationKind::Timed {
+                    per_level: true,
+                    ..
+                }
+            ),
+            Self::Dismissible(_) => duration.dismissible,
+            Self::Discharge(_) => duration.discharge,
+        };
+        self.state().test_match(matches)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, FilterReprMacro)]
+pub enum CastingTimeType {
+    Swift(FilterState),
+    Immediate(FilterState),
+    Move(FilterState),
+    Standard(FilterState),
+    #[name = "Full-Round"]
+    FullRound(FilterState),
+    Rounds(FilterState),
+    Minutes(FilterState),
+    /// An hour or longer.
+    Hours(FilterState),
+    #[name = "See Text"]
+    Special(FilterState),
+}
+
+impl CastingTimeType {
+    pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
+        use crate::spell::{CastingTime, TimeUnit};
+
+        let time = spell.casting_time_value;
+        let unit = match time {
+            CastingTime::Timed { unit, .. } => Some(unit),
+            _ => None,
+        };
+        let matches = match self {
+            Self::Swift(_) => time == CastingTime::Swift,
+            Self::Immediate(_) => time == CastingTime::Immediate,
+            Self::Move(_) => time == CastingTime::Move,
+            Self::Standard(_) => time == CastingTime::Standard,
+            Self::FullRound(_) => time == CastingTime::FullRound,
+            Self::Rounds(_) => unit == Some(TimeUnit::Round),
+            Self::Minutes(_) => unit == Some(TimeUnit::Minute),
+            Self::Hours(_) => unit.is_some_and(|u| u >= TimeUnit::Hour),
+            Self::Special(_) => time == CastingTime::Special,
+        };
+        self.state().test_match(matches)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, FilterReprMacro)]
+pub enum AreaShape {
+    Burst(FilterState),
+    Emanation(FilterState),
+    Spread(FilterState),
+    Cone(FilterState),
+    Line(FilterState),
+    Cylinder(FilterState),
+    Wall(FilterState),
+    Cube(FilterState),
+    Sphere(FilterState),
+    /// The size or the number of shapes grows with the caster level.
+    #[name = "Per Level"]
+    PerLevel(FilterState),
+}
+
+impl AreaShape {
+    pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
+        use crate::spell::Shape;
+
+        let Some(area) = spell.area_value else {
+            return self.state().test_match(false);
+        };
+        let matches = match self {
+            Self::Burst(_) => area.shape == Shape::Burst,
+            Self::Emanation(_) => area.shape == Shape::Emanation,
+            Self::Spread(_) => area.shape == Shape::Spread,
+            Self::Cone(_) => area.shape == Shape::Cone,
+            Self::Line(_) => area.shape == Shape::Line,
+            Self::Cylinder(_) => area.shape == Shape::Cylinder,
+            Self::Wall(_) => area.shape == Shape::Wall,
+            Self::Cube(_) => area.shape == Shape::Cube,
+            Self::Sphere(_) => area.shape == Shape::Sphere,
+            Self::PerLevel(_) => area.per_level,
+        };
+        self.state().test_match(matches)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, FilterReprMacro)]
+pub enum TargetType {
+    You(FilterState),
+    #[name = "One Target"]
+    One(FilterState),
+    #[name = "Multiple Targets"]
+    Multiple(FilterState),
+    #[name = "See Text"]
+    Special(FilterState),
+}
+
+impl TargetType {
+    pub fn special_test(&self, spell: &crate::spell::Spell) -> bool {
+        use crate::spell::TargetCount;
+
+        let matches = match self {
+            Self::You(_) => spell.target_count == TargetCount::You,
+            Self::One(_) => spell.target_count == TargetCount::One,
+            Self::Multiple(_) => spell.target_count == TargetCount::Multiple,
+            Self::Special(_) => spell.target_count == TargetCount::Special,
+        };
+        self.state().test_match(matches)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, FilterReprMacro)]
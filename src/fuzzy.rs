@@ -0,0 +1,166 @@
+//! Typo tolerant matching of spell names.
+
+use std::collections::HashSet;
+
+use crate::spell::{Spell, ALL_SPELLS};
+
+/// Lower case words with a trailing roman numeral as digits and inverted names turned
+/// around, so "Summon Monster I" and "summon monster 1" or "Cure Light Wounds, Mass" and
+/// "mass cure light wounds" are the same.
+pub fn normalize(name: &str) -> String {
+    let name = name.to_lowercase();
+    let name = match name.split_once(", ") {
+        Some((base, prefix)) => format!("{prefix} {base}"),
+        None => name,
+    };
+    let mut words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect();
+    // a numeral is the last word of a name like "Summon Monster IV", anywhere else "i" or
+    // "v" is more likely a word being typed
+    if let [_, .., last] = &mut words[..] {
+        if let Some(n) = roman(last) {
+            *last = n.to_string();
+        }
+    }
+    words.join(" ")
+}
+
+/// Whether `text` is part of the name, as typed or both normalized.
+pub fn contains(name: &str, text: &str) -> bool {
+    name.to_lowercase().contains(&text.to_lowercase()) || normalize(name).contains(&normalize(text))
+}
+
+/// Whether every word of `text` is close to a word of the name, the last one may be
+/// unfinished.
+pub fn matches(name: &str, text: &str) -> bool {
+    distance(name, text).is_some()
+}
+
+/// The names closest to `text`, best first.
+pub fn suggestions(text: &str, count: usize) -> Vec<&'static Spell> {
+    let mut scored: Vec<(usize, &Spell)> = ALL_SPELLS
+        .iter()
+        .filter_map(|spell| Some((distance(&spell.name, text)?, spell)))
+        .collect();
+    scored.sort_by_key(|(distance, spell)| (*distance, spell.name.len()));
+    // some spells are listed once per source
+    let mut seen = HashSet::new();
+    scored
+        .into_iter()
+        .filter(|(_, spell)| seen.insert(&spell.name))
+        .take(count)
+        .map(|(_, spell)| spell)
+        .collect()
+}
+
+/// Summed edit distance of the words of `text` to their closest name words, `None` if a
+/// word has no close match.
+fn distance(name: &str, text: &str) -> Option<usize> {
+    let name = normalize(name);
+    let name_words: Vec<&str> = name.split(' ').collect();
+    let text = normalize(text);
+    let words: Vec<&str> = text.split(' ').filter(|w| !w.is_empty()).collect();
+    let mut total = 0;
+    for (i, word) in words.iter().enumerate() {
+        let last = i + 1 == words.len();
+        let best = name_words
+            .iter()
+            .map(|name_word| {
+                let full = edit_distance(word, name_word);
+                if last {
+                    // "magic mis" is on its way to "magic missile"
+                    let prefix: String = name_word.chars().take(word.chars().count()).collect();
+                    full.min(edit_distance(word, &prefix))
+                } else {
+                    full
+                }
+            })
+            .min()?;
+        if best > tolerance(word) {
+            return None;
+        }
+        total += best;
+    }
+    Some(total)
+}
+
+/// Allowed typos in a word, numbers and short words have to be exact.
+fn tolerance(word: &str) -> usize {
+    match word.chars().count() {
+        _ if word.chars().all(|c| c.is_ascii_digit()) => 0,
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Optimal string alignment distance, a swap of two neighboring letters counts as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = d;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+fn roman(word: &str) -> Option<u32> {
+    const NUMERALS: [&str; 9] = ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix"];
+    NUMERALS
+        .iter()
+        .position(|n| *n == word)
+        .map(|i| i as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_trailing_numerals() {
+        assert_eq!(normalize("Summon Monster IV"), "summon monster 4");
+        assert_eq!(
+            normalize("Cure Light Wounds, Mass"),
+            "mass cure light wounds"
+        );
+        assert_eq!(normalize("i"), "i");
+        assert_eq!(normalize("Vision I Saw"), "vision i saw");
+    }
+
+    #[test]
+    fn contains_letters_and_numerals() {
+        assert!(contains("Invisibility", "i"));
+        assert!(contains("Invisibility", "v"));
+        assert!(contains("Summon Monster IV", "summon monster 4"));
+        assert!(contains("Summon Monster IV", "monster iv"));
+        assert!(contains("Cure Light Wounds, Mass", "mass cure"));
+        assert!(!contains("Summon Monster IV", "summon monster 5"));
+    }
+
+    #[test]
+    fn matches_typos() {
+        assert!(matches("Magic Missile", "magic misile"));
+        assert!(matches("Magic Missile", "magic mis"));
+        assert!(!matches("Summon Monster IV", "summon monster 5"));
+        assert_eq!(edit_distance("fierball", "fireball"), 1);
+    }
+}
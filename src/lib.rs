@@ -1,9 +1,21 @@
 #![warn(clippy::all, rust_2018_idioms)]
 mod app;
-mod filters;
-mod spell;
+mod cards;
+mod casting;
+mod export;
+pub mod filter_expr;
+pub mod filters;
+mod fuzzy;
+mod query;
+mod query_parser;
+mod search;
+mod sorting;
+pub mod spell;
+mod spellbook;
 mod util;
 pub use app::SpellSearchApp;
+pub use query::{LevelRange, MinDuration, SpellQuery};
+pub use query_parser::QueryError;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
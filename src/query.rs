@@ -0,0 +1,641 @@
+use std::{collections::HashMap, ops::Range};
+
+use filter_repr::{FilterRepr, FilterState};
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    filter_expr::{FilterExpr, Predicate},
+    filters::{
+        AreaShape, CastingTimeType, Domain, DurationType, Level, Save, SpellComponent,
+        SpellDescriptor, SpellFlag, SpellRange, SpellResistance, SpellSource, Spellschool,
+        Subschool, TargetType,
+    },
+    fuzzy, search,
+    spell::{ClassType, Spell, TimeUnit, ALL_SPELLS},
+};
+
+/// The complete filter state of a spell search, independent of any GUI.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SpellQuery {
+    /// Part of the spell name, if no name contains it names with a few typos match instead.
+    pub name: String,
+    /// Comma separated keywords the description has to contain, `-word` it must not contain,
+    /// `a|b` either of, `"a phrase"` or a `/regex/`. Call [`SpellQuery::update_keywords`] after
+    /// changing this.
+    pub description: String,
+    #[serde(skip)]
+    keywords: Vec<Keyword>,
+    /// Why a keyword could not be used, it is left out until fixed.
+    #[serde(skip)]
+    keyword_error: Option<String>,
+    /// Full text search over names and descriptions for words, `"phrases"` and `prefixes*`,
+    /// it only ranks the spells and doesn't hide any. Call [`SpellQuery::update_keywords`]
+    /// after changing this.
+    pub search: String,
+    /// Relevance of the spells containing any term of `search` by id, `None` without a search.
+    #[serde(skip)]
+    relevance: Option<HashMap<u32, f64>>,
+    pub school: Vec<Spellschool>,
+    pub school_or: bool,
+    pub level: Vec<Level>,
+    pub level_or: bool,
+    pub subschool: Vec<Subschool>,
+    pub subschool_or: bool,
+    pub domain: Vec<Domain>,
+    pub domain_or: bool,
+    pub descriptor: Vec<SpellDescriptor>,
+    pub descriptor_or: bool,
+    pub components: Vec<SpellComponent>,
+    pub components_or: bool,
+    /// Only spells whose material components cost at most this many gp.
+    pub max_cost: Option<u32>,
+    pub casting_time: Vec<CastingTimeType>,
+    pub casting_time_or: bool,
+    pub range: Vec<SpellRange>,
+    pub range_or: bool,
+    /// Only spells reaching at least this far.
+    pub min_range: Option<MinRange>,
+    pub duration: Vec<DurationType>,
+    pub duration_or: bool,
+    /// Only spells lasting at least this long.
+    pub min_duration: Option<MinDuration>,
+    pub area: Vec<AreaShape>,
+    pub area_or: bool,
+    /// Only spells whose area or effect is at least this large.
+    pub min_area: Option<MinArea>,
+    pub targets: Vec<TargetType>,
+    pub targets_or: bool,
+    pub save: Vec<Save>,
+    pub save_or: bool,
+    pub spell_res: Vec<SpellResistance>,
+    pub spell_res_or: bool,
+    pub flags: Vec<SpellFlag>,
+    pub flags_or: bool,
+    pub source: Vec<SpellSource>,
+    pub source_or: bool,
+    pub selected_classes: Vec<ClassType>,
+    pub class_or: bool,
+    /// Numeric level ranges, all of them have to match.
+    pub level_ranges: Vec<LevelRange>,
+    /// Additional conditions that are ANDed with the filter rows, see
+    /// [`SpellQuery::filter_expr`].
+    pub expr: FilterExpr,
+}
+
+impl Default for SpellQuery {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            description: String::new(),
+            keywords: Vec::new(),
+            keyword_error: None,
+            search: String::new(),
+            relevance: None,
+            school: Spellschool::get_all(),
+            school_or: false,
+            level: Level::get_all(),
+            level_or: false,
+            subschool: Subschool::get_all(),
+            subschool_or: false,
+            domain: Domain::get_all(),
+            domain_or: false,
+            descriptor: SpellDescriptor::get_all(),
+            descriptor_or: false,
+            components: SpellComponent::get_all(),
+            components_or: false,
+            max_cost: None,
+            casting_time: CastingTimeType::get_all(),
+            casting_time_or: false,
+            range: SpellRange::get_all(),
+            range_or: false,
+            min_range: None,
+            duration: DurationType::get_all(),
+            duration_or: false,
+            min_duration: None,
+            area: AreaShape::get_all(),
+            area_or: false,
+            min_area: None,
+            targets: TargetType::get_all(),
+            targets_or: false,
+            save: Save::get_all(),
+            save_or: false,
+            spell_res: SpellResistance::get_all(),
+            spell_res_or: false,
+            flags: SpellFlag::get_all(),
+            flags_or: false,
+            source: SpellSource::get_all(),
+            source_or: false,
+            selected_classes: ClassType::get_all(),
+            class_or: false,
+            level_ranges: Vec::new(),
+            expr: FilterExpr::default(),
+        }
+    }
+}
+
+impl SpellQuery {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Recompiles the description keywords and reruns the full text search, call this after
+    /// changing `description` or `search`.
+    pub fn update_keywords(&mut self) {
+        self.relevance = if self.search.trim().is_empty() {
+            None
+        } else {
+            search::INDEX.search(&self.search)
+        };
+        self.keywords.clear();
+        self.keyword_error = None;
+        for text in split_keywords(&self.description, ',') {
+            match Keyword::parse(text) {
+                Ok(keyword) => self.keywords.push(keyword),
+                Err(e) => {
+                    self.keyword_error.get_or_insert(format!("{text}: {e}"));
+                }
+            }
+        }
+    }
+
+    /// The first description keyword that could not be used and why.
+    pub fn keyword_error(&self) -> Option<&str> {
+        self.keyword_error.as_deref()
+    }
+
+    /// Sorted, non overlapping byte ranges of `text` that the description keywords or the name
+    /// match, to show why a spell was found.
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .keywords
+            .iter()
+            .filter(|k| !k.excluded)
+            .flat_map(|k| k.regex.find_iter(text).map(|m| m.range()))
+            .collect();
+        // ASCII lower casing keeps the byte offsets
+        let name = self.name.trim().to_ascii_lowercase();
+        if !name.is_empty() {
+            let lower = text.to_ascii_lowercase();
+            ranges.extend(lower.match_indices(&name).map(|(i, _)| i..i + name.len()));
+        }
+        ranges.retain(|r| !r.is_empty());
+        ranges.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// Brings filter rows of a query saved by an older version up to date, so values that
+    /// were added since show up.
+    pub fn add_missing_filters(&mut self) {
+        fn update<T: FilterRepr + Clone>(filters: &mut Vec<T>) {
+            *filters = T::get_all()
+                .into_iter()
+                .map(|f| {
+                    filters
+                        .iter()
+                        .find(|old| old.name() == f.name())
+                        .cloned()
+                        .unwrap_or(f)
+                })
+                .collect();
+        }
+
+        update(&mut self.school);
+        update(&mut self.level);
+        update(&mut self.subschool);
+        update(&mut self.domain);
+        update(&mut self.descriptor);
+        update(&mut self.components);
+        update(&mut self.casting_time);
+        update(&mut self.range);
+        update(&mut self.duration);
+        update(&mut self.area);
+        update(&mut self.targets);
+        update(&mut self.save);
+        update(&mut self.spell_res);
+        update(&mut self.flags);
+        update(&mut self.source);
+        update(&mut self.selected_classes);
+    }
+
+    /// Runs the query over all spells, returning each match with its level string.
+    ///
+    /// If no spell name contains `name` it is compared typo tolerantly, so "magic misile"
+    /// still finds Magic Missile.
+    pub fn run(&self) -> Vec<(&'static Spell, String)> {
+        let expr = self.filter_expr();
+        let fuzzy_name = self.fuzzy_name();
+        ALL_SPELLS
+            .iter()
+            .filter_map(|spell| {
+                let level = spell.level_string(&self.selected_classes);
+                self.test(spell, &level, &expr, fuzzy_name)
+                    .then_some((spell, level))
+            })
+            .collect()
+    }
+
+    /// Whether `spell` is part of the result of [`SpellQuery::run`].
+    pub fn matches(&self, spell: &Spell) -> bool {
+        let level = spell.level_string(&self.selected_classes);
+        self.test(spell, &level, &self.filter_expr(), self.fuzzy_name())
+    }
+
+    /// Whether the name is compared typo tolerantly, only the name decides this so other
+    /// filters emptying the result don't turn on the fallback.
+    fn fuzzy_name(&self) -> bool {
+        !self.name.trim().is_empty()
+            && !ALL_SPELLS
+                .iter()
+                .any(|spell| fuzzy::contains(&spell.name, &self.name))
+    }
+
+    /// Names close to `name` for a "did you mean", best first.
+    pub fn suggestions(&self, count: usize) -> Vec<&'static str> {
+        fuzzy::suggestions(&self.name, count)
+            .into_iter()
+            .map(|spell| spell.name.as_str())
+            .collect()
+    }
+
+    /// The BM25 score of `spell` for the full text search, higher is more relevant.
+    pub fn relevance(&self, spell: &Spell) -> Option<f64> {
+        self.relevance.as_ref()?.get(&spell.id).copied()
+    }
+
+    /// Whether the query has a full text search to rank the spells by.
+    pub fn has_search(&self) -> bool {
+        self.relevance.is_some()
+    }
+
+    /// The filter rows, limits and level ranges as a single expression, ANDed with `expr`.
+    /// Rows combined with OR become an OR of their set values, excluded values a NOT.
+    pub fn filter_expr(&self) -> FilterExpr {
+        let mut items = Vec::new();
+        push_row(
+            &mut items,
+            &self.selected_classes,
+            self.class_or,
+            Predicate::Class,
+        );
+        push_row(&mut items, &self.level, self.level_or, Predicate::Level);
+        push_row(&mut items, &self.school, self.school_or, Predicate::School);
+        push_row(
+            &mut items,
+            &self.subschool,
+            self.subschool_or,
+            Predicate::Subschool,
+        );
+        push_row(&mut items, &self.domain, self.domain_or, Predicate::Domain);
+        push_row(&mut items, &self.save, self.save_or, Predicate::Save);
+        push_row(
+            &mut items,
+            &self.spell_res,
+            self.spell_res_or,
+            Predicate::SpellResistance,
+        );
+        push_row(
+            &mut items,
+            &self.components,
+            self.components_or,
+            Predicate::Component,
+        );
+        push_row(
+            &mut items,
+            &self.descriptor,
+            self.descriptor_or,
+            Predicate::Descriptor,
+        );
+        items.extend(
+            self.max_cost
+                .map(|c| FilterExpr::Leaf(Predicate::MaxCost(c))),
+        );
+        push_row(
+            &mut items,
+            &self.casting_time,
+            self.casting_time_or,
+            Predicate::CastingTime,
+        );
+        push_row(&mut items, &self.range, self.range_or, Predicate::Range);
+        items.extend(
+            self.min_range
+                .map(|r| FilterExpr::Leaf(Predicate::MinRange(r))),
+        );
+        push_row(
+            &mut items,
+            &self.duration,
+            self.duration_or,
+            Predicate::Duration,
+        );
+        items.extend(
+            self.min_duration
+                .map(|d| FilterExpr::Leaf(Predicate::MinDuration(d))),
+        );
+        push_row(&mut items, &self.area, self.area_or, Predicate::Area);
+        items.extend(
+            self.min_area
+                .map(|a| FilterExpr::Leaf(Predicate::MinArea(a))),
+        );
+        push_row(
+            &mut items,
+            &self.targets,
+            self.targets_or,
+            Predicate::Targets,
+        );
+        push_row(&mut items, &self.flags, self.flags_or, Predicate::Flag);
+        push_row(&mut items, &self.source, self.source_or, Predicate::Source);
+        items.extend(
+            self.level_ranges
+                .iter()
+                .map(|r| r.expr(&self.selected_classes)),
+        );
+        match &self.expr {
+            FilterExpr::And(children) => items.extend(children.iter().cloned()),
+            expr => items.push(expr.clone()),
+        }
+        FilterExpr::And(items)
+    }
+
+    /// Tests `spell` against the name, keywords and `expr` from
+    /// [`SpellQuery::filter_expr`], `level` is the level string shown in the table.
+    fn test(&self, spell: &Spell, level: &str, expr: &FilterExpr, fuzzy_name: bool) -> bool {
+        let name_test = if fuzzy_name {
+            fuzzy::matches
+        } else {
+            fuzzy::contains
+        };
+        name_test(&spell.name, &self.name)
+            && self.keywords.iter().all(|k| k.test(&spell.description))
+            && expr.test(spell, level)
+    }
+}
+
+/// An inclusive numeric spell level range, unlike the `Level` row this doesn't
+/// depend on matching the level string.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LevelRange {
+    /// The (`Positive`) class whose level is tested, `None` tests the levels of
+    /// the selected classes, or `sla_level` if no class is selected, like the table shows.
+    pub class: Option<ClassType>,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Default for LevelRange {
+    fn default() -> Self {
+        Self {
+            class: None,
+            min: 0,
+            max: 9,
+        }
+    }
+}
+
+impl LevelRange {
+    /// The range as expression, without a class it tests the levels of the selected classes,
+    /// or the SLA level if no class is selected.
+    pub fn expr(&self, selected_classes: &[ClassType]) -> FilterExpr {
+        let class_level = |class: &ClassType| {
+            FilterExpr::Leaf(Predicate::ClassLevel {
+                class: class.clone(),
+                min: self.min,
+                max: self.max,
+            })
+        };
+        match &self.class {
+            Some(class) => class_level(class),
+            None => {
+                let selected: Vec<FilterExpr> = selected_classes
+                    .iter()
+                    .filter(|c| *c.state() == FilterState::Positive)
+                    .map(class_level)
+                    .collect();
+                if !selected.is_empty() {
+                    return FilterExpr::Or(selected);
+                }
+                // without a class the level string is the SLA level
+                FilterExpr::Or(
+                    Level::get_all()
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(l, _)| (self.min..=self.max).contains(&(*l as u32)))
+                        .map(|(_, l)| {
+                            FilterExpr::Leaf(Predicate::Level(l.with_state(FilterState::Positive)))
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// A minimum range in feet at a caster level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct MinRange {
+    pub feet: u32,
+    pub caster_level: u32,
+}
+
+impl Default for MinRange {
+    fn default() -> Self {
+        Self {
+            feet: 30,
+            caster_level: 1,
+        }
+    }
+}
+
+impl MinRange {
+    pub fn test(&self, spell: &Spell) -> bool {
+        spell
+            .range_value
+            .feet(self.caster_level)
+            .is_some_and(|feet| feet >= self.feet as f64)
+    }
+}
+
+impl std::fmt::Display for MinRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}ft@cl{}", self.feet, self.caster_level)
+    }
+}
+
+/// A minimum duration like "10 min./level".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct MinDuration {
+    pub amount: u32,
+    pub unit: TimeUnit,
+    pub per_level: bool,
+}
+
+impl Default for MinDuration {
+    fn default() -> Self {
+        Self {
+            amount: 10,
+            unit: TimeUnit::Minute,
+            per_level: true,
+        }
+    }
+}
+
+impl MinDuration {
+    /// Tests whether the spell lasts at least as long at every caster level.
+    pub fn test(&self, spell: &Spell) -> bool {
+        // both durations grow linearly with the caster level, so the ends are enough
+        [1, 20].into_iter().all(|caster_level| {
+            spell
+                .duration_value
+                .rounds(caster_level)
+                .is_some_and(|rounds| rounds >= self.rounds(caster_level))
+        })
+    }
+
+    fn rounds(&self, caster_level: u32) -> f64 {
+        let levels = if self.per_level { caster_level } else { 1 };
+        (self.amount as u64 * self.unit.rounds() * levels as u64) as f64
+    }
+}
+
+impl std::fmt::Display for MinDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.amount, self.unit.name())?;
+        if self.per_level {
+            write!(f, "/level")?;
+        }
+        Ok(())
+    }
+}
+
+/// A minimum radius or length of an area or effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct MinArea {
+    pub feet: u32,
+}
+
+impl Default for MinArea {
+    fn default() -> Self {
+        Self { feet: 20 }
+    }
+}
+
+impl MinArea {
+    pub fn test(&self, spell: &Spell) -> bool {
+        spell
+            .area_value
+            .and_then(|a| a.feet)
+            .is_some_and(|feet| feet >= self.feet)
+    }
+}
+
+impl std::fmt::Display for MinArea {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}ft", self.feet)
+    }
+}
+
+/// Adds the set values of a filter row to `items`, as a single OR if `or` is set.
+fn push_row<T: FilterRepr>(
+    items: &mut Vec<FilterExpr>,
+    filters: &[T],
+    or: bool,
+    predicate: fn(T) -> Predicate,
+) {
+    let row = filters.iter().filter(|f| f.some_filter()).map(|f| {
+        let leaf = FilterExpr::Leaf(predicate(f.with_state(FilterState::Positive)));
+        if *f.state() == FilterState::Negative {
+            FilterExpr::Not(Box::new(leaf))
+        } else {
+            leaf
+        }
+    });
+    if or {
+        let row: Vec<FilterExpr> = row.collect();
+        if !row.is_empty() {
+            items.push(FilterExpr::Or(row));
+        }
+    } else {
+        items.extend(row);
+    }
+}
+
+/// One comma separated part of [`SpellQuery::description`].
+#[derive(Debug, Clone)]
+pub(crate) struct Keyword {
+    regex: Regex,
+    /// `-word`, the description must not contain it.
+    excluded: bool,
+}
+
+impl Keyword {
+    /// Compiles `-word`, `a|b`, `"a phrase"` or `/regex/`, all case insensitive. Words and
+    /// phrases only match whole words.
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let (excluded, text) = match text.strip_prefix('-') {
+            Some(rest) if !rest.trim().is_empty() => (true, rest.trim_start()),
+            _ => (false, text),
+        };
+        let pattern = match text.strip_prefix('/') {
+            Some(regex) => regex
+                .strip_suffix('/')
+                .ok_or("missing closing '/'")?
+                .to_string(),
+            None => {
+                let mut alternatives = Vec::new();
+                for alternative in split_keywords(text, '|') {
+                    let phrase = match alternative.strip_prefix('"') {
+                        Some(quoted) => quoted.strip_suffix('"').ok_or("missing closing '\"'")?,
+                        None => alternative,
+                    };
+                    let words: Vec<String> = phrase.split_whitespace().map(regex::escape).collect();
+                    if !words.is_empty() {
+                        alternatives.push(words.join(r"\s+"));
+                    }
+                }
+                if alternatives.is_empty() {
+                    return Err("empty keyword".to_string());
+                }
+                format!(r"\b(?:{})\b", alternatives.join("|"))
+            }
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { regex, excluded })
+    }
+
+    fn test(&self, description: &str) -> bool {
+        self.regex.is_match(description) != self.excluded
+    }
+}
+
+/// Splits keywords at `separator`, except inside `"quotes"` and `/regexes/`. Parts are trimmed
+/// and empty ones left out.
+pub(crate) fn split_keywords(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        let part = text[start..i].trim();
+        let unsigned = part.strip_prefix('-').unwrap_or(part).trim_start();
+        // a regex ends at a '/' right before the separator
+        let regex = unsigned.starts_with('/');
+        let regex_open = regex && (unsigned.len() < 2 || !unsigned.ends_with('/'));
+        if c == '"' && !regex {
+            quoted = !quoted;
+        } else if c == separator && !quoted && !regex_open {
+            parts.push(part);
+            start = i + c.len_utf8();
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
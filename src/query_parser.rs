@@ -0,0 +1,1190 @@
+use std::{fmt::Display, ops::Range};
+
+use filter_repr::{FilterRepr, FilterState};
+
+use crate::{
+    filter_expr::{FilterExpr, Predicate},
+    filters::Level,
+    query::{split_keywords, Keyword, LevelRange, MinArea, MinDuration, MinRange, SpellQuery},
+    spell::{ClassType, TimeUnit},
+};
+
+/// An error in a query string, `span` is the byte range of the offending text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (at {}..{})",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A single `[-]key:value|value` term of a query string.
+#[derive(Debug)]
+struct Term {
+    negated: bool,
+    key: Option<(String, Range<usize>)>,
+    values: Vec<(String, Range<usize>)>,
+    span: Range<usize>,
+}
+
+#[derive(Debug)]
+enum Token {
+    Term(Term),
+    /// `(` or `-(`, starting a group
+    Open {
+        negated: bool,
+        span: Range<usize>,
+    },
+    Close(Range<usize>),
+    /// the `OR` keyword between the conditions of a group
+    Or(Range<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Class,
+    Level,
+    School,
+    Subschool,
+    Domain,
+    Descriptor,
+    Component,
+    Cost,
+    CastingTime,
+    Range,
+    Duration,
+    Area,
+    Targets,
+    Save,
+    SpellResistance,
+    Flag,
+    Source,
+}
+
+impl SpellQuery {
+    /// Parses a query string like `class:wiz level:<=3 -descriptor:evil source:"PFRPG Core"`.
+    ///
+    /// Words without a key search the spell name, `desc:` adds description keywords and
+    /// `search:` full text search terms, values with spaces are searched as phrase.
+    /// Values of a term can be combined with `|`, which switches the whole row to OR.
+    ///
+    /// Level comparisons like `level:<=3` or `level:wizard2-4` become [`LevelRange`]s,
+    /// `range:>=100ft@cl5` sets a [`MinRange`], `duration:>=10min/level` a [`MinDuration`]
+    /// and `area:>=20ft` a [`MinArea`], `cost:<=500` limits the material component cost.
+    ///
+    /// `list:"fire domain"` keeps spells on a list whose name contains the value, besides the
+    /// classes these are the domains, bloodlines, patrons and mysteries granting spells.
+    ///
+    /// Parentheses build a [`FilterExpr`] instead, e.g.
+    /// `(descriptor:fire OR descriptor:cold) -(school:evo level:wizard>=5)`,
+    /// inside them `level:wizard<=3` restricts the level for a single class.
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let mut query = Self::new();
+        let mut names = Vec::new();
+        let mut keywords = Vec::new();
+        let mut searches = Vec::new();
+        let mut groups = Vec::new();
+        // first term that switched a row to OR and first excluded value, per row
+        let mut or_spans: Vec<(Row, Range<usize>)> = Vec::new();
+        let mut negative_spans: Vec<(Row, Range<usize>)> = Vec::new();
+
+        let mut tokens = tokenize(text)?.into_iter();
+        while let Some(token) = tokens.next() {
+            let term = match token {
+                Token::Term(term) => term,
+                Token::Open { negated, span } => {
+                    let group = parse_group(&mut tokens, span)?;
+                    groups.push(if negated {
+                        FilterExpr::Not(Box::new(group))
+                    } else {
+                        group
+                    });
+                    continue;
+                }
+                Token::Close(span) => return Err(QueryError::new(span, "unmatched ')'")),
+                Token::Or(span) => {
+                    return Err(QueryError::new(
+                        span,
+                        "OR is only allowed inside parentheses",
+                    ))
+                }
+            };
+            let key = term.key.as_ref().map(|(k, _)| k.to_lowercase());
+            let row = match key.as_deref() {
+                None | Some("name") | Some("n") => {
+                    if term.negated {
+                        return Err(QueryError::new(term.span, "names can not be excluded"));
+                    }
+                    names.extend(term.values.into_iter().map(|(v, _)| v));
+                    continue;
+                }
+                Some("desc") | Some("description") | Some("text") => {
+                    // values are keywords themselves, `desc:"fire, -evil"` adds two of them
+                    let values: Vec<String> = term.values.into_iter().map(|(v, _)| v).collect();
+                    let sign = if term.negated { "-" } else { "" };
+                    let keyword = format!("{sign}{}", values.join("|"));
+                    for part in split_keywords(&keyword, ',') {
+                        Keyword::parse(part).map_err(|e| QueryError::new(term.span.clone(), e))?;
+                    }
+                    keywords.push(keyword);
+                    continue;
+                }
+                Some("search") | Some("find") => {
+                    if term.negated {
+                        return Err(QueryError::new(
+                            term.span,
+                            "search terms can not be excluded",
+                        ));
+                    }
+                    for (value, _) in term.values {
+                        searches.push(if value.contains(char::is_whitespace) {
+                            format!("\"{value}\"")
+                        } else {
+                            value
+                        });
+                    }
+                    continue;
+                }
+                // spell lists have no filter row, they only exist as expression
+                Some("list") => {
+                    groups.push(term_expr(&term)?);
+                    continue;
+                }
+                Some(k) => row(k).ok_or_else(|| {
+                    let (k, span) = term.key.clone().unwrap_or_default();
+                    QueryError::new(span, format!("unknown key '{k}'"))
+                })?,
+            };
+            if row == Row::Level {
+                if let (false, [(value, span)]) = (term.negated, &term.values[..]) {
+                    if is_class_level(value) || value.parse::<u32>().is_err() {
+                        let range = level_range(value)
+                            .map_err(|message| QueryError::new(span.clone(), message))?;
+                        query.level_ranges.push(range);
+                        continue;
+                    }
+                }
+                // excluded or alternative levels of a single class only exist as expression
+                if term.values.iter().any(|(v, _)| is_class_level(v)) {
+                    groups.push(term_expr(&term)?);
+                    continue;
+                }
+            }
+            if row == Row::Cost {
+                match (term.negated, &term.values[..]) {
+                    (false, [(value, span)]) if query.max_cost.is_none() => {
+                        let cost = max_cost(value)
+                            .map_err(|message| QueryError::new(span.clone(), message))?;
+                        query.max_cost = Some(cost);
+                    }
+                    _ => groups.push(term_expr(&term)?),
+                }
+                continue;
+            }
+            if matches!(row, Row::Range | Row::Duration | Row::Area)
+                && term.values.iter().any(|(v, _)| is_minimum(v))
+            {
+                // only a single minimum per row fits into the query, others are expressions
+                match (term.negated, &term.values[..], row) {
+                    (false, [(value, span)], Row::Range) if query.min_range.is_none() => {
+                        let range = min_range(value)
+                            .map_err(|message| QueryError::new(span.clone(), message))?;
+                        query.min_range = Some(range);
+                    }
+                    (false, [(value, span)], Row::Duration) if query.min_duration.is_none() => {
+                        let duration = min_duration(value)
+                            .map_err(|message| QueryError::new(span.clone(), message))?;
+                        query.min_duration = Some(duration);
+                    }
+                    (false, [(value, span)], Row::Area) if query.min_area.is_none() => {
+                        let area = min_area(value)
+                            .map_err(|message| QueryError::new(span.clone(), message))?;
+                        query.min_area = Some(area);
+                    }
+                    _ => groups.push(term_expr(&term)?),
+                }
+                continue;
+            }
+
+            let or = match row {
+                Row::Class => apply(&mut query.selected_classes, &term)?,
+                Row::Level => apply_level(&mut query.level, &term)?,
+                Row::School => apply(&mut query.school, &term)?,
+                Row::Subschool => apply(&mut query.subschool, &term)?,
+                Row::Domain => apply(&mut query.domain, &term)?,
+                Row::Descriptor => apply(&mut query.descriptor, &term)?,
+                Row::Component => apply(&mut query.components, &term)?,
+                Row::Cost => unreachable!("costs are handled above"),
+                Row::CastingTime => apply(&mut query.casting_time, &term)?,
+                Row::Range => apply(&mut query.range, &term)?,
+                Row::Duration => apply(&mut query.duration, &term)?,
+                Row::Area => apply(&mut query.area, &term)?,
+                Row::Targets => apply(&mut query.targets, &term)?,
+                Row::Save => apply(&mut query.save, &term)?,
+                Row::SpellResistance => apply(&mut query.spell_res, &term)?,
+                Row::Flag => apply(&mut query.flags, &term)?,
+                Row::Source => apply(&mut query.source, &term)?,
+            };
+            if or {
+                match row {
+                    Row::Class => query.class_or = true,
+                    Row::Level => query.level_or = true,
+                    Row::School => query.school_or = true,
+                    Row::Subschool => query.subschool_or = true,
+                    Row::Domain => query.domain_or = true,
+                    Row::Descriptor => query.descriptor_or = true,
+                    Row::Component => query.components_or = true,
+                    Row::Cost => unreachable!("costs are handled above"),
+                    Row::CastingTime => query.casting_time_or = true,
+                    Row::Range => query.range_or = true,
+                    Row::Duration => query.duration_or = true,
+                    Row::Area => query.area_or = true,
+                    Row::Targets => query.targets_or = true,
+                    Row::Save => query.save_or = true,
+                    Row::SpellResistance => query.spell_res_or = true,
+                    Row::Flag => query.flags_or = true,
+                    Row::Source => query.source_or = true,
+                }
+                if !or_spans.iter().any(|(r, _)| *r == row) {
+                    or_spans.push((row, term.span.clone()));
+                }
+            }
+            if term.negated && !negative_spans.iter().any(|(r, _)| *r == row) {
+                negative_spans.push((row, term.span.clone()));
+            }
+        }
+
+        // excluded values can't be expressed in a row that is combined with OR
+        for (row, or_span) in &or_spans {
+            if let Some((_, negative_span)) = negative_spans.iter().find(|(r, _)| r == row) {
+                let span = if negative_span.start > or_span.start {
+                    negative_span
+                } else {
+                    or_span
+                };
+                return Err(QueryError::new(
+                    span.clone(),
+                    "excluded values can not be combined with alternatives",
+                ));
+            }
+        }
+
+        query.name = names.join(" ");
+        query.description = keywords.join(",");
+        query.search = searches.join(" ");
+        query.expr = if matches!(groups[..], [FilterExpr::And(_) | FilterExpr::Or(_)]) {
+            groups.remove(0)
+        } else {
+            FilterExpr::And(groups)
+        };
+        Ok(query)
+    }
+
+    /// Formats the query in the syntax understood by [`SpellQuery::parse`].
+    pub fn to_query_string(&self) -> String {
+        let mut terms = Vec::new();
+        if !self.name.is_empty() {
+            terms.push(quote_value(&self.name));
+        }
+        push_row(&mut terms, "class", &self.selected_classes, self.class_or);
+        push_row(&mut terms, "level", &self.level, self.level_or);
+        for range in &self.level_ranges {
+            let levels = match &range.class {
+                Some(class) => format!(
+                    "{}{}",
+                    class.name().to_lowercase(),
+                    format_levels(range.min, range.max)
+                ),
+                // a single number would be a `Level` filter
+                None if range.min == range.max => format!("{}-{}", range.min, range.max),
+                None => format_levels(range.min, range.max),
+            };
+            terms.push(format!("level:{}", quote_value(&levels)));
+        }
+        push_row(&mut terms, "school", &self.school, self.school_or);
+        push_row(&mut terms, "subschool", &self.subschool, self.subschool_or);
+        push_row(&mut terms, "domain", &self.domain, self.domain_or);
+        push_row(
+            &mut terms,
+            "descriptor",
+            &self.descriptor,
+            self.descriptor_or,
+        );
+        push_row(
+            &mut terms,
+            "component",
+            &self.components,
+            self.components_or,
+        );
+        if let Some(cost) = self.max_cost {
+            terms.push(format!("cost:<={cost}"));
+        }
+        push_row(
+            &mut terms,
+            "casting",
+            &self.casting_time,
+            self.casting_time_or,
+        );
+        push_row(&mut terms, "range", &self.range, self.range_or);
+        if let Some(range) = &self.min_range {
+            terms.push(format!("range:>={range}"));
+        }
+        push_row(&mut terms, "duration", &self.duration, self.duration_or);
+        if let Some(duration) = &self.min_duration {
+            terms.push(format!("duration:>={duration}"));
+        }
+        push_row(&mut terms, "area", &self.area, self.area_or);
+        if let Some(area) = &self.min_area {
+            terms.push(format!("area:>={area}"));
+        }
+        push_row(&mut terms, "targets", &self.targets, self.targets_or);
+        push_row(&mut terms, "save", &self.save, self.save_or);
+        push_row(&mut terms, "sr", &self.spell_res, self.spell_res_or);
+        push_row(&mut terms, "flag", &self.flags, self.flags_or);
+        push_row(&mut terms, "source", &self.source, self.source_or);
+        for keyword in split_keywords(&self.description, ',') {
+            let (sign, keyword) = match keyword.strip_prefix('-') {
+                Some(rest) if !rest.trim().is_empty() => ("-", rest.trim_start()),
+                _ => ("", keyword),
+            };
+            let values: Vec<String> = if keyword.starts_with('/') {
+                vec![quote_value(keyword)]
+            } else {
+                split_keywords(keyword, '|')
+                    .into_iter()
+                    .map(quote_value)
+                    .collect()
+            };
+            terms.push(format!("{sign}desc:{}", values.join("|")));
+        }
+        // every second part of the search is a quoted phrase
+        for (i, part) in self.search.split('"').enumerate() {
+            if i % 2 == 1 {
+                if !part.trim().is_empty() {
+                    terms.push(format!("search:{}", quote_value(part.trim())));
+                }
+            } else {
+                for word in part.split_whitespace() {
+                    terms.push(format!("search:{}", quote_value(word)));
+                }
+            }
+        }
+        if !self.expr.is_empty() {
+            terms.push(format_group(&self.expr));
+        }
+        terms.join(" ")
+    }
+}
+
+fn row(key: &str) -> Option<Row> {
+    Some(match key {
+        "class" | "cls" | "c" => Row::Class,
+        "level" | "lvl" | "l" => Row::Level,
+        "school" | "s" => Row::School,
+        "subschool" | "sub" => Row::Subschool,
+        "domain" => Row::Domain,
+        "descriptor" => Row::Descriptor,
+        "component" | "comp" => Row::Component,
+        "cost" => Row::Cost,
+        "casting" | "cast" => Row::CastingTime,
+        "range" => Row::Range,
+        "duration" | "dur" => Row::Duration,
+        "area" | "effect" => Row::Area,
+        "targets" | "target" => Row::Targets,
+        "save" => Row::Save,
+        "sr" => Row::SpellResistance,
+        "flag" | "is" => Row::Flag,
+        "source" | "src" => Row::Source,
+        _ => return None,
+    })
+}
+
+/// Parses the tokens after an opening parenthesis up to the matching `)`.
+///
+/// Conditions are ANDed, `OR` binds weaker, so `a b OR c` is `(a AND b) OR c`.
+fn parse_group(
+    tokens: &mut impl Iterator<Item = Token>,
+    open: Range<usize>,
+) -> Result<FilterExpr, QueryError> {
+    let mut alternatives = vec![Vec::new()];
+    loop {
+        let item = match tokens.next() {
+            None => return Err(QueryError::new(open, "unclosed '('")),
+            Some(Token::Close(span)) => {
+                if alternatives.len() > 1 && alternatives.last().is_some_and(Vec::is_empty) {
+                    return Err(QueryError::new(span, "missing condition after OR"));
+                }
+                break;
+            }
+            Some(Token::Or(span)) => {
+                if alternatives.last().is_some_and(Vec::is_empty) {
+                    return Err(QueryError::new(span, "missing condition before OR"));
+                }
+                alternatives.push(Vec::new());
+                continue;
+            }
+            Some(Token::Open { negated, span }) => {
+                let group = parse_group(tokens, span)?;
+                if negated {
+                    FilterExpr::Not(Box::new(group))
+                } else {
+                    group
+                }
+            }
+            Some(Token::Term(term)) => term_expr(&term)?,
+        };
+        if let Some(items) = alternatives.last_mut() {
+            items.push(item);
+        }
+    }
+
+    let mut alternatives: Vec<FilterExpr> = alternatives
+        .into_iter()
+        .map(|mut items| {
+            if items.len() == 1 {
+                items.remove(0)
+            } else {
+                FilterExpr::And(items)
+            }
+        })
+        .collect();
+    Ok(if alternatives.len() == 1 {
+        alternatives.remove(0)
+    } else {
+        FilterExpr::Or(alternatives)
+    })
+}
+
+/// Turns a term inside a group into an expression, `|` alternatives become an OR.
+fn term_expr(term: &Term) -> Result<FilterExpr, QueryError> {
+    let key = term.key.as_ref().map(|(k, _)| k.to_lowercase());
+    let list = key.as_deref() == Some("list");
+    let row = match key.as_deref() {
+        None | Some("name") | Some("n") | Some("list") => None,
+        Some("desc") | Some("description") | Some("text") => {
+            return Err(QueryError::new(
+                term.span.clone(),
+                "description keywords can not be used inside parentheses",
+            ))
+        }
+        Some("search") | Some("find") => {
+            return Err(QueryError::new(
+                term.span.clone(),
+                "search terms can not be used inside parentheses",
+            ))
+        }
+        Some(k) => Some(row(k).ok_or_else(|| {
+            let (k, span) = term.key.clone().unwrap_or_default();
+            QueryError::new(span, format!("unknown key '{k}'"))
+        })?),
+    };
+
+    let mut leaves = Vec::new();
+    for (value, span) in &term.values {
+        let predicates = match row {
+            None if list => vec![Predicate::List(value.clone())],
+            None => vec![Predicate::Name(value.clone())],
+            Some(row) => {
+                predicates(row, value).map_err(|message| QueryError::new(span.clone(), message))?
+            }
+        };
+        leaves.extend(predicates.into_iter().map(FilterExpr::Leaf));
+    }
+    let expr = if leaves.len() == 1 {
+        leaves.remove(0)
+    } else {
+        FilterExpr::Or(leaves)
+    };
+    Ok(if term.negated {
+        FilterExpr::Not(Box::new(expr))
+    } else {
+        expr
+    })
+}
+
+/// The predicates matching `value`, several (to be ORed) for level comparisons.
+fn predicates(row: Row, value: &str) -> Result<Vec<Predicate>, String> {
+    fn one<T: FilterRepr>(value: &str, f: fn(T) -> Predicate) -> Result<Vec<Predicate>, String> {
+        let all = T::get_all();
+        let index = find_filter(&all, value)?;
+        Ok(vec![f(all[index].with_state(FilterState::Positive))])
+    }
+
+    match row {
+        Row::Class => one(value, Predicate::Class),
+        Row::Level if is_class_level(value) => {
+            let (class, levels) = class_level(value)?;
+            Ok(vec![Predicate::ClassLevel {
+                class,
+                min: levels.start as u32,
+                max: levels.end as u32 - 1,
+            }])
+        }
+        Row::Level => {
+            let levels = parse_levels(value).ok_or_else(|| format!("invalid level '{value}'"))?;
+            let all = Level::get_all();
+            Ok(levels
+                .map(|l| Predicate::Level(all[l].with_state(FilterState::Positive)))
+                .collect())
+        }
+        Row::School => one(value, Predicate::School),
+        Row::Subschool => one(value, Predicate::Subschool),
+        Row::Domain => one(value, Predicate::Domain),
+        Row::Descriptor => one(value, Predicate::Descriptor),
+        Row::Component => one(value, Predicate::Component),
+        Row::Cost => Ok(vec![Predicate::MaxCost(max_cost(value)?)]),
+        Row::CastingTime => one(value, Predicate::CastingTime),
+        Row::Range if is_minimum(value) => Ok(vec![Predicate::MinRange(min_range(value)?)]),
+        Row::Range => one(value, Predicate::Range),
+        Row::Duration if is_minimum(value) => {
+            Ok(vec![Predicate::MinDuration(min_duration(value)?)])
+        }
+        Row::Duration => one(value, Predicate::Duration),
+        Row::Area if is_minimum(value) => Ok(vec![Predicate::MinArea(min_area(value)?)]),
+        Row::Area => one(value, Predicate::Area),
+        Row::Targets => one(value, Predicate::Targets),
+        Row::Save => one(value, Predicate::Save),
+        Row::SpellResistance => one(value, Predicate::SpellResistance),
+        Row::Flag => one(value, Predicate::Flag),
+        Row::Source => one(value, Predicate::Source),
+    }
+}
+
+fn is_class_level(value: &str) -> bool {
+    value.starts_with(char::is_alphabetic)
+}
+
+fn level_range(value: &str) -> Result<LevelRange, String> {
+    let (class, levels) = if is_class_level(value) {
+        let (class, levels) = class_level(value)?;
+        (Some(class), levels)
+    } else {
+        let levels = parse_levels(value).ok_or_else(|| format!("invalid level '{value}'"))?;
+        (None, levels)
+    };
+    Ok(LevelRange {
+        class,
+        min: levels.start as u32,
+        max: levels.end as u32 - 1,
+    })
+}
+
+/// Parses `wizard<=3`, `cleric2-4`, ... into the class and its levels.
+fn class_level(value: &str) -> Result<(ClassType, Range<usize>), String> {
+    let split = value
+        .find(|c: char| c.is_ascii_digit() || matches!(c, '<' | '>'))
+        .ok_or_else(|| format!("missing level after '{value}'"))?;
+    let (class, levels) = value.split_at(split);
+    let classes = ClassType::get_all();
+    let class = classes[find_filter(&classes, class)?].with_state(FilterState::Positive);
+    let levels = parse_levels(levels).ok_or_else(|| format!("invalid level '{levels}'"))?;
+    Ok((class, levels))
+}
+
+fn is_minimum(value: &str) -> bool {
+    value.starts_with(">=")
+}
+
+/// Parses `>=100ft@cl5` or `>=30ft` (at caster level 1) into a minimum range.
+fn min_range(value: &str) -> Result<MinRange, String> {
+    let invalid = || format!("invalid range '{value}', expected e.g. '>=100ft@cl5'");
+    let value = value.strip_prefix(">=").ok_or_else(invalid)?.to_lowercase();
+    let (feet, caster_level) = match value.split_once('@') {
+        Some((feet, level)) => {
+            let level = level.strip_prefix("cl").unwrap_or(level);
+            (feet, level.trim().parse().map_err(|_| invalid())?)
+        }
+        None => (value.as_str(), 1),
+    };
+    let feet = feet
+        .trim()
+        .trim_end_matches('.')
+        .trim_end_matches("ft")
+        .trim_end_matches("feet")
+        .trim();
+    Ok(MinRange {
+        feet: feet.parse().map_err(|_| invalid())?,
+        caster_level,
+    })
+}
+
+/// Parses `<=500` or `<=500gp` into a maximum material cost in gp.
+fn max_cost(value: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid cost '{value}', expected e.g. '<=500'");
+    let value = value.strip_prefix("<=").unwrap_or(value).to_lowercase();
+    value
+        .trim()
+        .trim_end_matches("gp")
+        .trim()
+        .parse()
+        .map_err(|_| invalid())
+}
+
+/// Parses `>=20ft` into a minimum radius or length.
+fn min_area(value: &str) -> Result<MinArea, String> {
+    let invalid = || format!("invalid size '{value}', expected e.g. '>=20ft'");
+    let value = value.strip_prefix(">=").ok_or_else(invalid)?.to_lowercase();
+    let feet = value
+        .trim()
+        .trim_end_matches('.')
+        .trim_end_matches("ft")
+        .trim_end_matches("feet")
+        .trim();
+    Ok(MinArea {
+        feet: feet.parse().map_err(|_| invalid())?,
+    })
+}
+
+/// Parses `>=10min/level`, `>=24hours`, ... into a minimum duration.
+fn min_duration(value: &str) -> Result<MinDuration, String> {
+    let invalid = || format!("invalid duration '{value}', expected e.g. '>=10min/level'");
+    let value = value.strip_prefix(">=").ok_or_else(invalid)?.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let (unit, per_level) = match unit.trim().strip_suffix("/level") {
+        Some(unit) => (unit, true),
+        None => (unit.trim(), false),
+    };
+    Ok(MinDuration {
+        amount: amount.parse().map_err(|_| invalid())?,
+        unit: TimeUnit::parse(unit).ok_or_else(invalid)?,
+        per_level,
+    })
+}
+
+/// Sets the filters named by the values of `term`, returns whether the row should use OR.
+fn apply<T: FilterRepr>(filters: &mut [T], term: &Term) -> Result<bool, QueryError> {
+    let state = if term.negated {
+        FilterState::Negative
+    } else {
+        FilterState::Positive
+    };
+    for (value, span) in &term.values {
+        let index = find_filter(filters, value)
+            .map_err(|message| QueryError::new(span.clone(), message))?;
+        filters[index] = filters[index].with_state(state.clone());
+    }
+    Ok(term.values.len() > 1)
+}
+
+/// Like [`apply`], but also understands `<3`, `<=3`, `>3`, `>=3` and `2-4`.
+fn apply_level(filters: &mut [Level], term: &Term) -> Result<bool, QueryError> {
+    let state = if term.negated {
+        FilterState::Negative
+    } else {
+        FilterState::Positive
+    };
+    let mut count = 0;
+    for (value, span) in &term.values {
+        let levels = parse_levels(value)
+            .ok_or_else(|| QueryError::new(span.clone(), format!("invalid level '{value}'")))?;
+        for level in levels {
+            filters[level] = filters[level].with_state(state.clone());
+            count += 1;
+        }
+    }
+    Ok(count > 1)
+}
+
+fn parse_levels(value: &str) -> Option<Range<usize>> {
+    let level = |s: &str| s.trim().parse::<usize>().ok().filter(|l| *l <= 9);
+    let range = if let Some(v) = value.strip_prefix("<=") {
+        0..level(v)? + 1
+    } else if let Some(v) = value.strip_prefix(">=") {
+        level(v)?..10
+    } else if let Some(v) = value.strip_prefix('<') {
+        0..level(v)?
+    } else if let Some(v) = value.strip_prefix('>') {
+        level(v)? + 1..10
+    } else if let Some((from, to)) = value.split_once('-') {
+        level(from)?..level(to)? + 1
+    } else {
+        let l = level(value)?;
+        l..l + 1
+    };
+    (!range.is_empty()).then_some(range)
+}
+
+/// Finds the filter whose name matches `value`, or starts with it if that is unique.
+/// Case, spaces and punctuation are ignored.
+fn find_filter<T: FilterRepr>(filters: &[T], value: &str) -> Result<usize, String> {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let wanted = normalize(value);
+    if wanted.is_empty() {
+        return Err("missing value".to_string());
+    }
+
+    let candidates: Vec<usize> = filters
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| normalize(f.name()).starts_with(&wanted))
+        .map(|(i, _)| i)
+        .collect();
+    if let Some(i) = candidates
+        .iter()
+        .find(|i| normalize(filters[**i].name()) == wanted)
+    {
+        return Ok(*i);
+    }
+    match candidates[..] {
+        [i] => Ok(i),
+        [] => Err(format!("unknown value '{value}'")),
+        _ => {
+            let options: Vec<&str> = candidates.iter().map(|i| filters[*i].name()).collect();
+            Err(format!(
+                "ambiguous value '{value}', could be any of: {}",
+                options.join(", ")
+            ))
+        }
+    }
+}
+
+fn push_row<T: FilterRepr>(terms: &mut Vec<String>, key: &str, filters: &[T], or: bool) {
+    let values = |state: FilterState| -> Vec<String> {
+        filters
+            .iter()
+            .filter(|f| *f.state() == state)
+            .map(|f| quote_value(&f.name().to_lowercase()))
+            .collect()
+    };
+
+    let positive = values(FilterState::Positive);
+    if or && positive.len() > 1 {
+        terms.push(format!("{key}:{}", positive.join("|")));
+    } else {
+        terms.extend(positive.iter().map(|v| format!("{key}:{v}")));
+    }
+    terms.extend(
+        values(FilterState::Negative)
+            .iter()
+            .map(|v| format!("-{key}:{v}")),
+    );
+}
+
+/// Formats an expression as a condition inside a group.
+fn format_expr(expr: &FilterExpr) -> String {
+    match expr {
+        FilterExpr::Leaf(predicate) => format_predicate(predicate),
+        FilterExpr::Not(inner) => match inner.as_ref() {
+            FilterExpr::Leaf(predicate) => format!("-{}", format_predicate(predicate)),
+            inner => format!("-{}", format_group(inner)),
+        },
+        group => format_group(group),
+    }
+}
+
+fn format_group(expr: &FilterExpr) -> String {
+    let join = |items: &[FilterExpr]| items.iter().map(format_expr).collect::<Vec<_>>().join(" ");
+    let content = match expr {
+        FilterExpr::And(items) => join(items),
+        FilterExpr::Or(alternatives) => alternatives
+            .iter()
+            .map(|alternative| match alternative {
+                // AND binds stronger than OR, no parentheses needed
+                FilterExpr::And(items) if !items.is_empty() => join(items),
+                other => format_expr(other),
+            })
+            .collect::<Vec<_>>()
+            .join(" OR "),
+        other => format_expr(other),
+    };
+    format!("({content})")
+}
+
+fn format_predicate(predicate: &Predicate) -> String {
+    fn term<T: FilterRepr>(key: &str, f: &T) -> String {
+        format!("{key}:{}", quote_value(&f.name().to_lowercase()))
+    }
+
+    match predicate {
+        Predicate::Name(name) => format!("name:{}", quote_value(name)),
+        Predicate::Class(f) => term("class", f),
+        Predicate::ClassLevel { class, min, max } => format!(
+            "level:{}",
+            quote_value(&format!(
+                "{}{}",
+                class.name().to_lowercase(),
+                format_levels(*min, *max)
+            ))
+        ),
+        Predicate::List(list) => format!("list:{}", quote_value(list)),
+        Predicate::Level(f) => term("level", f),
+        Predicate::School(f) => term("school", f),
+        Predicate::Subschool(f) => term("subschool", f),
+        Predicate::Domain(f) => term("domain", f),
+        Predicate::Descriptor(f) => term("descriptor", f),
+        Predicate::Component(f) => term("component", f),
+        Predicate::MaxCost(cost) => format!("cost:<={cost}"),
+        Predicate::CastingTime(f) => term("casting", f),
+        Predicate::Range(f) => term("range", f),
+        Predicate::MinRange(range) => format!("range:>={range}"),
+        Predicate::Duration(f) => term("duration", f),
+        Predicate::MinDuration(duration) => format!("duration:>={duration}"),
+        Predicate::Area(f) => term("area", f),
+        Predicate::MinArea(area) => format!("area:>={area}"),
+        Predicate::Targets(f) => term("targets", f),
+        Predicate::Save(f) => term("save", f),
+        Predicate::SpellResistance(f) => term("sr", f),
+        Predicate::Flag(f) => term("flag", f),
+        Predicate::Source(f) => term("source", f),
+    }
+}
+
+fn format_levels(min: u32, max: u32) -> String {
+    if min == max {
+        min.to_string()
+    } else if min == 0 {
+        format!("<={max}")
+    } else if max >= 9 {
+        format!(">={min}")
+    } else {
+        format!("{min}-{max}")
+    }
+}
+
+fn quote_value(value: &str) -> String {
+    if value.is_empty()
+        || value == "OR"
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '|' | ':' | '\\' | '(' | ')'))
+    {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == ')' {
+            chars.next();
+            tokens.push(Token::Close(start..start + 1));
+            continue;
+        }
+
+        let negated = c == '-';
+        if negated {
+            chars.next();
+        }
+        if let Some(&(i, '(')) = chars.peek() {
+            chars.next();
+            tokens.push(Token::Open {
+                negated,
+                span: start..i + 1,
+            });
+            continue;
+        }
+
+        // a plain word is either the key or (if no ':' follows) the first value
+        let word_start = chars.peek().map_or(text.len(), |(i, _)| *i);
+        let mut word_end = word_start;
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || matches!(c, ':' | '"' | '|' | ')') {
+                break;
+            }
+            chars.next();
+            word_end = i + c.len_utf8();
+        }
+
+        let mut key = None;
+        let mut values = Vec::new();
+        let mut value_start = word_start;
+        if let Some(&(i, ':')) = chars.peek() {
+            key = Some((text[word_start..word_end].to_string(), word_start..word_end));
+            chars.next();
+            value_start = i + 1;
+        } else if word_end > word_start {
+            // the word is the start of the first value, continue reading it below
+            value_start = word_end;
+        }
+
+        let mut value = if key.is_none() {
+            text[word_start..word_end].to_string()
+        } else {
+            String::new()
+        };
+        let mut span_start = if key.is_none() {
+            word_start
+        } else {
+            value_start
+        };
+        let mut pos = value_start;
+        loop {
+            match chars.peek() {
+                Some(&(i, '"')) => {
+                    chars.next();
+                    let mut closed = false;
+                    while let Some((j, c)) = chars.next() {
+                        match c {
+                            '"' => {
+                                closed = true;
+                                pos = j + 1;
+                                break;
+                            }
+                            '\\' => {
+                                if let Some((k, escaped)) = chars.next() {
+                                    value.push(escaped);
+                                    pos = k + escaped.len_utf8();
+                                }
+                            }
+                            c => {
+                                value.push(c);
+                                pos = j + c.len_utf8();
+                            }
+                        }
+                    }
+                    if !closed {
+                        return Err(QueryError::new(i..text.len(), "unterminated quote"));
+                    }
+                }
+                Some(&(i, '|')) => {
+                    chars.next();
+                    values.push((std::mem::take(&mut value), span_start..pos));
+                    span_start = i + 1;
+                    pos = i + 1;
+                }
+                Some(&(_, c)) if c.is_whitespace() || c == ')' => break,
+                Some(&(i, c)) => {
+                    chars.next();
+                    value.push(c);
+                    pos = i + c.len_utf8();
+                }
+                None => break,
+            }
+        }
+        values.push((value, span_start..pos));
+
+        let span = start..pos;
+        if let Some((_, empty_span)) = values.iter().find(|(v, _)| v.is_empty()) {
+            let span = if empty_span.is_empty() {
+                span
+            } else {
+                empty_span.clone()
+            };
+            return Err(QueryError::new(span, "missing value"));
+        }
+        if !negated && key.is_none() && &text[span.clone()] == "OR" {
+            tokens.push(Token::Or(span));
+            continue;
+        }
+        tokens.push(Token::Term(Term {
+            negated,
+            key,
+            values,
+            span,
+        }));
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::{SpellDescriptor, Spellschool};
+
+    fn positive<T: FilterRepr>(filters: &[T]) -> Vec<&'static str> {
+        filters
+            .iter()
+            .filter(|f| *f.state() == FilterState::Positive)
+            .map(|f| f.name())
+            .collect()
+    }
+
+    fn negative<T: FilterRepr>(filters: &[T]) -> Vec<&'static str> {
+        filters
+            .iter()
+            .filter(|f| *f.state() == FilterState::Negative)
+            .map(|f| f.name())
+            .collect()
+    }
+
+    fn filter<T: FilterRepr>(value: &str) -> T {
+        let all = T::get_all();
+        all[find_filter(&all, value).unwrap()].with_state(FilterState::Positive)
+    }
+
+    fn error(text: &str) -> String {
+        SpellQuery::parse(text).unwrap_err().message
+    }
+
+    /// Parses `text`, formats it again and checks that parsing the result gives the same query.
+    fn round_trip(text: &str) {
+        let query = SpellQuery::parse(text).unwrap();
+        let formatted = query.to_query_string();
+        let reparsed = SpellQuery::parse(&formatted)
+            .unwrap_or_else(|e| panic!("can't parse '{formatted}' formatted from '{text}': {e}"));
+        assert_eq!(format!("{reparsed:?}"), format!("{query:?}"), "{formatted}");
+        assert_eq!(reparsed.to_query_string(), formatted);
+    }
+
+    #[test]
+    fn parses_rows() {
+        let query = SpellQuery::parse("fire ball class:wiz -school:evo level:3").unwrap();
+        assert_eq!(query.name, "fire ball");
+        assert_eq!(positive(&query.selected_classes), ["Wizard"]);
+        assert_eq!(negative(&query.school), ["Evocation"]);
+        assert_eq!(positive(&query.level), ["3"]);
+        assert!(!query.class_or && !query.school_or && !query.level_or);
+        assert!(query.expr.is_empty());
+    }
+
+    #[test]
+    fn parses_alternatives() {
+        let query = SpellQuery::parse("descriptor:fire|cold level:<=2").unwrap();
+        assert_eq!(positive(&query.descriptor), ["Cold", "Fire"]);
+        assert!(query.descriptor_or);
+        assert_eq!(
+            query.level_ranges,
+            [LevelRange {
+                class: None,
+                min: 0,
+                max: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_class_levels() {
+        let query = SpellQuery::parse("level:wizard2-4").unwrap();
+        let [range] = &query.level_ranges[..] else {
+            panic!("expected one range, got {:?}", query.level_ranges);
+        };
+        assert_eq!(range.class.as_ref().map(|c| c.name()), Some("Wizard"));
+        assert_eq!((range.min, range.max), (2, 4));
+        assert_eq!(parse_levels(">=7"), Some(7..10));
+        assert_eq!(parse_levels("<3"), Some(0..3));
+        assert_eq!(parse_levels("4-2"), None);
+        assert_eq!(parse_levels("10"), None);
+    }
+
+    #[test]
+    fn parses_groups() {
+        let query =
+            SpellQuery::parse("(descriptor:fire OR descriptor:cold) -(school:evo level:>=5)")
+                .unwrap();
+        let fire = filter::<SpellDescriptor>("fire");
+        let cold = filter::<SpellDescriptor>("cold");
+        let evocation = filter::<Spellschool>("evo");
+        let levels = (5..10)
+            .map(|l| FilterExpr::Leaf(Predicate::Level(filter::<Level>(&l.to_string()))))
+            .collect();
+        assert_eq!(
+            query.expr,
+            FilterExpr::And(vec![
+                FilterExpr::Or(vec![
+                    FilterExpr::Leaf(Predicate::Descriptor(fire)),
+                    FilterExpr::Leaf(Predicate::Descriptor(cold)),
+                ]),
+                FilterExpr::Not(Box::new(FilterExpr::And(vec![
+                    FilterExpr::Leaf(Predicate::School(evocation)),
+                    FilterExpr::Or(levels),
+                ]))),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_lists() {
+        let query = SpellQuery::parse("-list:\"fire domain\"").unwrap();
+        let list = FilterExpr::Leaf(Predicate::List("fire domain".to_string()));
+        assert_eq!(
+            query.expr,
+            FilterExpr::And(vec![FilterExpr::Not(Box::new(list))])
+        );
+    }
+
+    #[test]
+    fn parses_keywords_and_searches() {
+        let query =
+            SpellQuery::parse(r#"desc:fire|cold -desc:evil search:"fire ball" find:burn"#).unwrap();
+        assert_eq!(query.description, "fire|cold,-evil");
+        assert_eq!(query.search, r#""fire ball" burn"#);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(error("fire)"), "unmatched ')'");
+        assert_eq!(error("(fire"), "unclosed '('");
+        assert_eq!(
+            error("fire OR ice"),
+            "OR is only allowed inside parentheses"
+        );
+        assert_eq!(error("(fire OR)"), "missing condition after OR");
+        assert_eq!(error("(OR fire)"), "missing condition before OR");
+        assert_eq!(error("-fire"), "names can not be excluded");
+        assert_eq!(error("-search:fire"), "search terms can not be excluded");
+        assert_eq!(error("foo:bar"), "unknown key 'foo'");
+        assert_eq!(error("school:xyz"), "unknown value 'xyz'");
+        assert_eq!(error("class:"), "missing value");
+        assert_eq!(error("name:\"fire"), "unterminated quote");
+        assert_eq!(
+            error("descriptor:fire|cold -descriptor:evil"),
+            "excluded values can not be combined with alternatives"
+        );
+    }
+
+    #[test]
+    fn reports_error_spans() {
+        let error = SpellQuery::parse("class:wiz school:xyz").unwrap_err();
+        assert_eq!(error.span, 17..20);
+        let error = SpellQuery::parse("fire )").unwrap_err();
+        assert_eq!(error.span, 5..6);
+    }
+
+    #[test]
+    fn round_trips() {
+        for text in [
+            "",
+            "fireball",
+            "\"fire ball\"",
+            "class:wiz -school:evo level:3",
+            "class:wiz|cleric level:1|2",
+            "level:<=3 level:cleric>=2 level:wizard5",
+            "level:3-3",
+            "cost:<=500 range:>=100ft@cl5 duration:>=10min/level area:>=20ft",
+            "desc:fire|cold -desc:evil desc:\"/fire ?ball/\"",
+            "search:\"fire ball\" search:burn",
+            "(descriptor:fire OR descriptor:cold) -(school:evo level:>=5)",
+            "(class:wiz level:wizard<=3 OR -(name:ice) source:\"PFRPG Core\")",
+            "-level:wizard1|cleric2",
+            "list:\"fire domain\" -list:mystery|patron",
+        ] {
+            round_trip(text);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Full text search over the spell texts, ranked with BM25.
+
+use std::collections::{BTreeMap, HashMap};
+
+use lazy_static::lazy_static;
+
+use crate::spell::{Spell, ALL_SPELLS};
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Words left out of searches, they would only add noise to the ranking.
+const STOP_WORDS: [&str; 17] = [
+    "a", "an", "and", "as", "at", "be", "by", "for", "in", "is", "it", "of", "on", "or", "the",
+    "to", "with",
+];
+
+lazy_static! {
+    /// Built on the first search, indexing takes a moment.
+    pub static ref INDEX: SearchIndex = SearchIndex::new(&ALL_SPELLS);
+}
+
+/// The occurrences of a term in one spell.
+struct Posting {
+    /// Index into the indexed spells.
+    doc: usize,
+    /// Number of occurrences, weighted by the field they are in.
+    frequency: f64,
+    /// Word positions over all fields, used for phrases.
+    positions: Vec<u32>,
+}
+
+/// One part of a search text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchTerm {
+    Word(String),
+    /// `word*` matches every word starting with `word`.
+    Prefix(String),
+    /// `"some words"` only match in this order.
+    Phrase(Vec<String>),
+}
+
+/// An inverted index over the name, short description, description and mythic text.
+pub struct SearchIndex {
+    /// Sorted, so prefixes are a range.
+    terms: BTreeMap<String, Vec<Posting>>,
+    ids: Vec<u32>,
+    /// Weighted number of words per spell.
+    lengths: Vec<f64>,
+    average_length: f64,
+}
+
+impl SearchIndex {
+    pub fn new(spells: &[Spell]) -> Self {
+        let mut terms: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+        let mut lengths = Vec::with_capacity(spells.len());
+        for (doc, spell) in spells.iter().enumerate() {
+            // a match in the name says more than one somewhere in the description
+            let fields = [
+                (&spell.name, 3.0),
+                (&spell.short_description, 2.0),
+                (&spell.description, 1.0),
+                (&spell.mythic_text, 1.0),
+            ];
+            let mut position = 0;
+            let mut length = 0.0;
+            for (text, weight) in fields {
+                for word in tokenize(text) {
+                    let postings = terms.entry(word).or_default();
+                    if postings.last().map_or(true, |p| p.doc != doc) {
+                        postings.push(Posting {
+                            doc,
+                            frequency: 0.0,
+                            positions: Vec::new(),
+                        });
+                    }
+                    if let Some(posting) = postings.last_mut() {
+                        posting.frequency += weight;
+                        posting.positions.push(position);
+                    }
+                    position += 1;
+                    length += weight;
+                }
+                // phrases don't continue into the next field
+                position += 1;
+            }
+            lengths.push(length);
+        }
+        let average_length = lengths.iter().sum::<f64>() / lengths.len().max(1) as f64;
+        Self {
+            terms,
+            ids: spells.iter().map(|s| s.id).collect(),
+            lengths,
+            average_length,
+        }
+    }
+
+    /// Scores the spells containing any term of `text` by [`Spell::id`], the more terms a
+    /// spell contains the higher it scores. `None` if `text` has no searchable terms.
+    pub fn search(&self, text: &str) -> Option<HashMap<u32, f64>> {
+        let terms = parse_search(text);
+        if terms.is_empty() {
+            return None;
+        }
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in &terms {
+            let frequencies = self.frequencies(term);
+            let idf = self.idf(frequencies.len());
+            for (doc, frequency) in frequencies {
+                *scores.entry(doc).or_default() += idf * self.saturate(doc, frequency);
+            }
+        }
+        Some(
+            scores
+                .into_iter()
+                .map(|(doc, score)| (self.ids[doc], score))
+                .collect(),
+        )
+    }
+
+    /// The (weighted) number of occurrences of `term` per spell that contains it.
+    fn frequencies(&self, term: &SearchTerm) -> HashMap<usize, f64> {
+        let mut frequencies = HashMap::new();
+        match term {
+            SearchTerm::Word(word) => {
+                for posting in self.terms.get(word).into_iter().flatten() {
+                    frequencies.insert(posting.doc, posting.frequency);
+                }
+            }
+            SearchTerm::Prefix(prefix) => {
+                let matching = self
+                    .terms
+                    .range(prefix.clone()..)
+                    .take_while(|(word, _)| word.starts_with(prefix.as_str()));
+                for (_, postings) in matching {
+                    for posting in postings {
+                        *frequencies.entry(posting.doc).or_default() += posting.frequency;
+                    }
+                }
+            }
+            SearchTerm::Phrase(words) => {
+                let Some(postings) = words
+                    .iter()
+                    .map(|w| self.terms.get(w))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return frequencies;
+                };
+                for first in postings[0] {
+                    let rest: Option<Vec<&Posting>> = postings[1..]
+                        .iter()
+                        .map(|p| {
+                            p.binary_search_by_key(&first.doc, |p| p.doc)
+                                .ok()
+                                .map(|i| &p[i])
+                        })
+                        .collect();
+                    let Some(rest) = rest else {
+                        continue;
+                    };
+                    let count = first
+                        .positions
+                        .iter()
+                        .filter(|start| {
+                            rest.iter().zip(1..).all(|(posting, offset)| {
+                                posting.positions.binary_search(&(*start + offset)).is_ok()
+                            })
+                        })
+                        .count();
+                    if count > 0 {
+                        frequencies.insert(first.doc, count as f64);
+                    }
+                }
+            }
+        }
+        frequencies
+    }
+
+    fn idf(&self, containing: usize) -> f64 {
+        let total = self.lengths.len() as f64;
+        let containing = containing as f64;
+        (1.0 + (total - containing + 0.5) / (containing + 0.5)).ln()
+    }
+
+    /// The BM25 term frequency part, long texts need more occurrences for the same score.
+    fn saturate(&self, doc: usize, frequency: f64) -> f64 {
+        let length = self.lengths[doc] / self.average_length;
+        frequency * (K1 + 1.0) / (frequency + K1 * (1.0 - B + B * length))
+    }
+}
+
+/// Splits a search text into words, `"quoted phrases"` and `prefixes*`.
+pub fn parse_search(text: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    // every second part is inside quotes
+    for (i, part) in text.split('"').enumerate() {
+        if i % 2 == 1 {
+            let mut words = tokenize(part);
+            match words.len() {
+                0 => {}
+                1 => terms.push(SearchTerm::Word(words.remove(0))),
+                _ => terms.push(SearchTerm::Phrase(words)),
+            }
+            continue;
+        }
+        for word in part.split_whitespace() {
+            match word.strip_suffix('*') {
+                Some(prefix) => {
+                    // the index only has stems, "fire*" has to find "fir" from "fire" too
+                    let prefix = normalize(prefix);
+                    let stemmed = stem(&prefix);
+                    let prefix = if prefix.starts_with(&stemmed) {
+                        stemmed
+                    } else {
+                        prefix
+                    };
+                    if !prefix.is_empty() {
+                        terms.push(SearchTerm::Prefix(prefix));
+                    }
+                }
+                None => terms.extend(
+                    tokenize(word)
+                        .into_iter()
+                        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+                        .map(SearchTerm::Word),
+                ),
+            }
+        }
+    }
+    terms
+}
+
+/// The stemmed, lower case words of `text`.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(stem)
+        .collect()
+}
+
+fn normalize(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// A light suffix stripper, enough to find "burning", "burned" and "burns" with "burn".
+fn stem(word: &str) -> String {
+    const SUFFIXES: [(&str, &str); 8] = [
+        ("ies", "y"),
+        ("sses", "ss"),
+        ("shes", "sh"),
+        ("ches", "ch"),
+        ("xes", "x"),
+        ("ing", ""),
+        ("ed", ""),
+        ("s", ""),
+    ];
+    let plural_like = ["ss", "us", "is"];
+    let mut stem = word.to_string();
+    for (suffix, replacement) in SUFFIXES {
+        if suffix == "s" && plural_like.iter().any(|p| word.ends_with(p)) {
+            continue;
+        }
+        let Some(base) = word.strip_suffix(suffix).filter(|b| b.len() >= 3) else {
+            continue;
+        };
+        stem = format!("{base}{replacement}");
+        // "stunned" -> "stun", but "called" stays "call"
+        let bytes = stem.as_bytes();
+        if matches!(suffix, "ing" | "ed")
+            && bytes[bytes.len() - 1] == bytes[bytes.len() - 2]
+            && !matches!(bytes[bytes.len() - 1], b'l' | b's' | b'z')
+        {
+            stem.pop();
+        }
+        break;
+    }
+    // "fire", "fires" and "fired" all become "fir"
+    if stem.len() > 3 && stem.ends_with('e') && !stem.ends_with("ee") {
+        stem.pop();
+    }
+    stem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stems_endings() {
+        for (word, expected) in [
+            ("burning", "burn"),
+            ("burned", "burn"),
+            ("burns", "burn"),
+            ("stunned", "stun"),
+            ("called", "call"),
+            ("fire", "fir"),
+            ("fires", "fir"),
+            ("abilities", "ability"),
+            ("bonus", "bonus"),
+            ("bless", "bless"),
+            ("tree", "tree"),
+        ] {
+            assert_eq!(stem(word), expected, "{word}");
+        }
+    }
+
+    #[test]
+    fn parses_phrases_and_prefixes() {
+        assert_eq!(
+            parse_search(r#"the "bonus to AC" evoc* fire* "Armor""#),
+            [
+                SearchTerm::Phrase(vec!["bonus".into(), "to".into(), "ac".into()]),
+                SearchTerm::Prefix("evoc".into()),
+                SearchTerm::Prefix("fir".into()),
+                SearchTerm::Word("armor".into()),
+            ]
+        );
+        assert!(parse_search("the * \"\"").is_empty());
+    }
+
+    #[test]
+    fn ranks_spells_with_more_terms_first() {
+        let spell = |id, name: &str, description: &str| Spell {
+            id,
+            name: name.to_string(),
+            short_description: String::new(),
+            description: description.to_string(),
+            mythic_text: String::new(),
+            ..ALL_SPELLS[0].clone()
+        };
+        let index = SearchIndex::new(&[
+            spell(1, "Heroism", "The subject gets a +2 morale bonus on saves."),
+            spell(2, "Shield", "You gain a +4 shield bonus to AC."),
+            spell(3, "Fireball", "A burst of flame deals fire damage."),
+        ]);
+
+        let scores = index.search("bonus AC").unwrap();
+        assert!(scores[&2] > scores[&1]);
+        assert!(!scores.contains_key(&3));
+
+        let scores = index.search(r#""bonus to AC""#).unwrap();
+        assert_eq!(scores.keys().collect::<Vec<_>>(), [&2]);
+        assert!(index.search("the").is_none());
+    }
+}
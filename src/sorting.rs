@@ -0,0 +1,52 @@
+//! Sort keys that follow the meaning of a column instead of its text.
+
+use std::cmp::Ordering;
+
+use filter_repr::{FilterRepr, FilterState};
+
+use crate::spell::{ClassType, RangeValue, Spell};
+
+/// Caster level at which level dependent ranges and durations are compared.
+const REFERENCE_CASTER_LEVEL: u32 = 10;
+
+/// Orders known values before unknown ones like "see text".
+pub fn cmp_known<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The lowest level of the spell for the selected classes, its SLA level if none is selected.
+pub fn level(spell: &Spell, classes: &[ClassType]) -> Option<u32> {
+    let mut selected = classes
+        .iter()
+        .filter(|c| *c.state() == FilterState::Positive)
+        .peekable();
+    if selected.peek().is_none() {
+        return Some(spell.sla_level);
+    }
+    selected.filter_map(|c| c.get_value(spell)).min()
+}
+
+/// Personal < Touch < Close < Medium < Long < Unlimited, fixed distances in between by
+/// their length in feet.
+pub fn range(spell: &Spell) -> Option<f64> {
+    match spell.range_value {
+        RangeValue::Personal => Some(-2.0),
+        RangeValue::Touch => Some(-1.0),
+        range => range.feet(REFERENCE_CASTER_LEVEL),
+    }
+}
+
+/// Length of the casting time in rounds, swift and immediate actions first.
+pub fn casting_time(spell: &Spell) -> Option<f64> {
+    spell.casting_time_value.rounds()
+}
+
+/// Length of the duration in rounds.
+pub fn duration(spell: &Spell) -> Option<f64> {
+    spell.duration_value.rounds(REFERENCE_CASTER_LEVEL)
+}
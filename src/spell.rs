@@ -3,3 +3,219 @@ use std::include;
 
 include!("../spell-build.rs");
 include!("../spell-generated.rs");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_class_names() {
+        for (name, class) in [
+            ("wizard", Class::Wizard),
+            (" Sorcerer", Class::Sorcerer),
+            ("summoner (unchained)", Class::UncSummoner),
+            ("warpriest", Class::Warpriest),
+            ("fire domain", Class::Domain("fire".to_string())),
+            (
+                "Draconic Bloodline",
+                Class::Bloodline("Draconic".to_string()),
+            ),
+            ("flame mystery", Class::Mystery("flame".to_string())),
+            (
+                "Evocation implement",
+                Class::Implement("Evocation".to_string()),
+            ),
+            (
+                "red mantis assassin",
+                Class::Other("red mantis assassin".to_string()),
+            ),
+        ] {
+            assert_eq!(Class::parse(name), class, "{name}");
+        }
+    }
+
+    #[test]
+    fn every_class_has_a_list() {
+        for class in ClassType::get_all() {
+            assert!(
+                !matches!(class.class(), Class::Other(_)),
+                "{class:?} has no list"
+            );
+        }
+    }
+}
+
+/// The parsers the build script fills the typed fields with.
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    include!("../spell-parse.rs");
+
+    #[test]
+    fn finds_quantities() {
+        for (text, expected) in [
+            ("10 min./level", Some((10, "min", true))),
+            ("1 round", Some((1, "round", false))),
+            ("range 1,000 ft.", Some((1000, "ft", false))),
+            ("400 ft. + 40 ft./level", Some((400, "ft", false))),
+            ("see text", None),
+            ("3", None),
+        ] {
+            assert_eq!(quantity(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_durations() {
+        let timed = |amount, unit, per_level| DurationKind::Timed {
+            amount,
+            unit,
+            per_level,
+        };
+        for (text, kind) in [
+            ("instantaneous", DurationKind::Instantaneous),
+            ("permanent (D)", DurationKind::Permanent),
+            ("concentration", DurationKind::Concentration),
+            (
+                "concentration, up to 1 min./level",
+                timed(1, TimeUnit::Minute, true),
+            ),
+            ("1 round/level", timed(1, TimeUnit::Round, true)),
+            ("24 hours", timed(24, TimeUnit::Hour, false)),
+            ("see text", DurationKind::Special),
+        ] {
+            assert_eq!(parse_duration(text, false).kind, kind, "{text}");
+        }
+
+        let duration = parse_duration("concentration, up to 1 min./level", false);
+        assert!(duration.concentration && !duration.dismissible && !duration.discharge);
+        let duration = parse_duration("10 min./level or until discharged (D)", false);
+        assert!(!duration.concentration && duration.dismissible && duration.discharge);
+        assert!(parse_duration("1 round", true).dismissible);
+    }
+
+    #[test]
+    fn parses_ranges() {
+        for (text, expected) in [
+            ("personal", RangeValue::Personal),
+            ("you", RangeValue::Personal),
+            ("touch", RangeValue::Touch),
+            ("close (25 ft. + 5 ft./2 levels)", RangeValue::Close),
+            ("medium (100 ft. + 10 ft./level)", RangeValue::Medium),
+            ("400 ft. + 40 ft./level", RangeValue::Long),
+            (
+                "60 ft.",
+                RangeValue::Fixed {
+                    feet: 60,
+                    per_level: false,
+                },
+            ),
+            (
+                "10 ft./level",
+                RangeValue::Fixed {
+                    feet: 10,
+                    per_level: true,
+                },
+            ),
+            (
+                "1 mile/level",
+                RangeValue::Fixed {
+                    feet: 5280,
+                    per_level: true,
+                },
+            ),
+            ("unlimited", RangeValue::Unlimited),
+            ("see text", RangeValue::Special),
+        ] {
+            assert_eq!(parse_range(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_shapes() {
+        let shape = |shape, feet, per_level| {
+            Some(SpellShape {
+                shape,
+                feet,
+                per_level,
+            })
+        };
+        for (text, expected) in [
+            (
+                "20-ft.-radius spread",
+                shape(Shape::Spread, Some(20), false),
+            ),
+            ("cone-shaped burst", shape(Shape::Cone, None, false)),
+            (
+                "cylinder (10-ft. radius, 40-ft. high)",
+                shape(Shape::Cylinder, Some(10), false),
+            ),
+            ("60-ft. line", shape(Shape::Line, Some(60), false)),
+            (
+                "wall up to 20 ft. long/level",
+                shape(Shape::Wall, Some(20), true),
+            ),
+            (
+                "up to four 10-ft. cubes",
+                shape(Shape::Cube, Some(10), false),
+            ),
+            ("one creature's outline", None),
+            ("one swarm", None),
+        ] {
+            assert_eq!(parse_shape(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn counts_targets() {
+        for (text, expected) in [
+            ("", TargetCount::None),
+            ("you", TargetCount::You),
+            ("one creature", TargetCount::One),
+            ("creature touched", TargetCount::One),
+            ("one creature/level", TargetCount::Multiple),
+            ("up to three creatures", TargetCount::Multiple),
+            (
+                "two creatures, no two of which can be more than 30 ft. apart",
+                TargetCount::Multiple,
+            ),
+            ("see text", TargetCount::Special),
+        ] {
+            assert_eq!(parse_targets(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_casting_times() {
+        let timed = |amount, unit| CastingTime::Timed { amount, unit };
+        for (text, expected) in [
+            ("1 standard action", CastingTime::Standard),
+            ("1 standard action or 1 round", CastingTime::Standard),
+            ("1 swift action", CastingTime::Swift),
+            ("1 immediate action", CastingTime::Immediate),
+            ("1 full-round action", CastingTime::FullRound),
+            ("1 round", timed(1, TimeUnit::Round)),
+            ("10 minutes", timed(10, TimeUnit::Minute)),
+            ("1 hour", timed(1, TimeUnit::Hour)),
+            ("see text", CastingTime::Special),
+        ] {
+            assert_eq!(parse_casting_time(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn finds_components() {
+        assert!(has_component("V, S, thought", "thought"));
+        assert!(has_component("V, Emotion, M (a tear)", "emotion"));
+        assert!(!has_component("V, M (a thought stone)", "thought"));
+    }
+
+    #[test]
+    fn splits_granted_lists() {
+        assert_eq!(
+            granted("Fire (4), Sun (5), Other"),
+            [("Fire".to_string(), 4), ("Sun".to_string(), 5)]
+        );
+    }
+}
@@ -0,0 +1,441 @@
+use filter_repr::{FilterRepr, FilterState};
+
+use crate::{
+    casting::{casting, casting_ability, Casting, SpellSlots},
+    spell::{ClassType, Spell, ALL_SPELLS},
+};
+
+/// A named list of spells of a single character.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Spellbook {
+    pub name: String,
+    /// Always `Positive`, so [`ClassType::get_value`] works on it.
+    pub class: ClassType,
+    pub caster_level: u32,
+    /// The [`Spell::id`]s of the contained spells.
+    pub spells: Vec<u32>,
+    /// The score of the casting ability, e.g. Intelligence for wizards.
+    #[serde(default = "default_ability_score")]
+    pub ability_score: u32,
+    /// Spells prepared for the day, only used by prepared casters.
+    #[serde(default)]
+    pub prepared: Vec<PreparedSpell>,
+    /// Used slots by spell level, only used by spontaneous casters.
+    #[serde(default)]
+    pub used: [u32; 10],
+}
+
+fn default_ability_score() -> u32 {
+    10
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct PreparedSpell {
+    pub id: u32,
+    pub level: u32,
+    pub cast: bool,
+}
+
+impl Spellbook {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            class: ClassType::Wizard(FilterState::Positive),
+            caster_level: 1,
+            spells: Vec::new(),
+            ability_score: default_ability_score(),
+            prepared: Vec::new(),
+            used: [0; 10],
+        }
+    }
+
+    pub fn slots(&self) -> SpellSlots {
+        SpellSlots::new(&self.class, self.caster_level, self.ability_score)
+    }
+
+    /// Whether a slot of `level` is free to prepare (or, for spontaneous casters, cast) a spell.
+    pub fn has_free_slot(&self, level: u32) -> bool {
+        let Some(per_day) = self.slots().per_day[level as usize] else {
+            return false;
+        };
+        let taken = match casting(&self.class) {
+            Casting::Prepared => self.prepared.iter().filter(|p| p.level == level).count() as u32,
+            Casting::Spontaneous => self.used[level as usize],
+        };
+        taken < per_day
+    }
+
+    /// Regains all slots, prepared spells stay prepared.
+    pub fn rest(&mut self) {
+        for prepared in &mut self.prepared {
+            prepared.cast = false;
+        }
+        self.used = [0; 10];
+    }
+
+    pub fn contains(&self, spell: &Spell) -> bool {
+        self.spells.contains(&spell.id)
+    }
+
+    pub fn add(&mut self, spell: &Spell) {
+        if !self.contains(spell) {
+            self.spells.push(spell.id);
+        }
+    }
+
+    pub fn remove(&mut self, spell: &Spell) {
+        self.spells.retain(|id| *id != spell.id);
+    }
+
+    /// The contained spells with their level for the spellbook's class,
+    /// sorted by that level and name.
+    pub fn spells(&self) -> Vec<(&'static Spell, Option<u32>)> {
+        let mut spells: Vec<(&'static Spell, Option<u32>)> = ALL_SPELLS
+            .iter()
+            .filter(|spell| self.contains(spell))
+            .map(|spell| (spell, self.class.get_value(spell)))
+            .collect();
+        spells.sort_by(|(a, a_level), (b, b_level)| {
+            a_level.cmp(b_level).then_with(|| a.name.cmp(&b.name))
+        });
+        spells
+    }
+
+    /// The spells per day with the prepared spells or used slots of each level.
+    fn slots_ui(&mut self, ui: &mut egui::Ui) {
+        let slots = self.slots();
+        let casting = casting(&self.class);
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Spells per Day").strong());
+            if ui
+                .button("Rest")
+                .on_hover_text("regain all slots")
+                .clicked()
+            {
+                self.rest();
+            }
+        });
+
+        let mut unprepared = None;
+        egui::Grid::new("spellbook_slots")
+            .striped(true)
+            .show(ui, |ui| {
+                for (level, per_day) in slots.per_day.iter().enumerate() {
+                    if level == 0 && slots.at_will_cantrips {
+                        ui.label("Level 0");
+                        ui.label("at will");
+                        ui.end_row();
+                        continue;
+                    }
+                    let Some(per_day) = *per_day else {
+                        continue;
+                    };
+                    ui.label(format!("Level {level}"));
+                    match casting {
+                        Casting::Spontaneous => {
+                            let used = &mut self.used[level];
+                            ui.label(format!("{} / {per_day}", per_day.saturating_sub(*used)))
+                                .on_hover_text("remaining / per day");
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .add_enabled(*used < per_day, egui::Button::new("Cast"))
+                                    .clicked()
+                                {
+                                    *used += 1;
+                                }
+                                if ui
+                                    .add_enabled(*used > 0, egui::Button::new("↺"))
+                                    .on_hover_text("undo")
+                                    .clicked()
+                                {
+                                    *used -= 1;
+                                }
+                            });
+                        }
+                        Casting::Prepared => {
+                            let prepared = self
+                                .prepared
+                                .iter()
+                                .filter(|p| p.level == level as u32)
+                                .count();
+                            ui.label(format!("{prepared} / {per_day}"))
+                                .on_hover_text("prepared / per day");
+                            ui.horizontal_wrapped(|ui| {
+                                for (i, p) in self.prepared.iter_mut().enumerate() {
+                                    if p.level != level as u32 {
+                                        continue;
+                                    }
+                                    let name = spell_by_id(p.id).map_or("?", |s| s.name.as_str());
+                                    if level == 0 {
+                                        // prepared 0-level spells aren't expended when cast
+                                        ui.label(name);
+                                    } else {
+                                        ui.checkbox(&mut p.cast, name).on_hover_text("cast");
+                                    }
+                                    if ui.small_button("🗑").on_hover_text("unprepare").clicked()
+                                    {
+                                        unprepared = Some(i);
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = unprepared {
+            self.prepared.remove(i);
+        }
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SpellbookWindow {
+    pub books: Vec<Spellbook>,
+    selected: usize,
+    /// Name of the spellbook the table is restricted to.
+    filter: Option<String>,
+    #[serde(skip)]
+    pub filters_changed: bool,
+}
+
+impl SpellbookWindow {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether `spell` passes the spellbook filter of the Sources window.
+    pub fn test(&self, spell: &Spell) -> bool {
+        match &self.filter {
+            Some(name) => self
+                .books
+                .iter()
+                .find(|b| b.name == *name)
+                .map_or(true, |b| b.contains(spell)),
+            None => true,
+        }
+    }
+
+    /// Shows the window, returns a spell the user clicked on.
+    pub fn spellbook_ui(
+        &mut self,
+        ctx: &egui::Context,
+        spellbook_open: &mut bool,
+    ) -> Option<&'static Spell> {
+        let mut clicked = None;
+        egui::containers::Window::new("Spellbooks")
+            .open(spellbook_open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("spellbook_select")
+                        .selected_text(
+                            self.books
+                                .get(self.selected)
+                                .map_or("", |b| b.name.as_str()),
+                        )
+                        .show_ui(ui, |ui| {
+                            for (i, book) in self.books.iter().enumerate() {
+                                ui.selectable_value(&mut self.selected, i, &book.name);
+                            }
+                        });
+                    if ui.button("New").clicked() {
+                        self.books.push(Spellbook::new(self.unique_name()));
+                        self.selected = self.books.len() - 1;
+                    }
+                    if ui
+                        .add_enabled(
+                            self.selected < self.books.len(),
+                            egui::Button::new("Delete"),
+                        )
+                        .clicked()
+                    {
+                        let book = self.books.remove(self.selected);
+                        if self.filter.as_ref() == Some(&book.name) {
+                            self.filter = None;
+                            self.filters_changed = true;
+                        }
+                        self.selected = self.selected.saturating_sub(1);
+                    }
+                });
+
+                let Some(book) = self.books.get_mut(self.selected) else {
+                    ui.label(
+                        "Add spells with a right click on the table or the 📖 button of a spell.",
+                    );
+                    return;
+                };
+                ui.separator();
+                egui::Grid::new("spellbook_properties")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        let old_name = book.name.clone();
+                        if ui.text_edit_singleline(&mut book.name).changed()
+                            && self.filter.as_ref() == Some(&old_name)
+                        {
+                            self.filter = Some(book.name.clone());
+                        }
+                        ui.end_row();
+
+                        ui.label("Class");
+                        egui::ComboBox::from_id_salt("spellbook_class")
+                            .selected_text(book.class.title())
+                            .height(300.0)
+                            .show_ui(ui, |ui| {
+                                for class in ClassType::get_all() {
+                                    let class = class.with_state(FilterState::Positive);
+                                    let title = class.title();
+                                    ui.selectable_value(&mut book.class, class, title);
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Caster Level");
+                        ui.add(egui::DragValue::new(&mut book.caster_level).range(1..=20));
+                        ui.end_row();
+
+                        ui.label(casting_ability(&book.class));
+                        ui.add(egui::DragValue::new(&mut book.ability_score).range(1..=60));
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                book.slots_ui(ui);
+
+                ui.separator();
+                let casting = casting(&book.class);
+                let spells = book.spells();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} spells", spells.len()));
+                    let costs = material_cost_summary(spells.iter().map(|(spell, _)| *spell));
+                    ui.label(format!("Material costs: {costs}"));
+                });
+                let mut removed = None;
+                egui::containers::ScrollArea::vertical()
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        egui::Grid::new("spellbook_spells")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (spell, level) in spells {
+                                    // spells that aren't on the class list have no level
+                                    ui.label(level.map_or("-".to_string(), |l| l.to_string()));
+                                    if ui.link(&spell.name).clicked() {
+                                        clicked = Some(spell);
+                                    }
+                                    let free = level.is_some_and(|l| book.has_free_slot(l));
+                                    let (text, hover) = match casting {
+                                        Casting::Prepared => {
+                                            ("Prepare", "prepare into a free slot")
+                                        }
+                                        Casting::Spontaneous => ("Cast", "use up a slot"),
+                                    };
+                                    if ui
+                                        .add_enabled(free, egui::Button::new(text).small())
+                                        .on_hover_text(hover)
+                                        .clicked()
+                                    {
+                                        if let Some(level) = level {
+                                            match casting {
+                                                Casting::Prepared => {
+                                                    book.prepared.push(PreparedSpell {
+                                                        id: spell.id,
+                                                        level,
+                                                        cast: false,
+                                                    })
+                                                }
+                                                Casting::Spontaneous => {
+                                                    book.used[level as usize] += 1
+                                                }
+                                            }
+                                        }
+                                    }
+                                    if ui.small_button("🗑").on_hover_text("remove").clicked() {
+                                        removed = Some(spell);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if let Some(spell) = removed {
+                    book.remove(spell);
+                    self.filters_changed = true;
+                }
+            });
+        clicked
+    }
+
+    /// Entries of a context menu to add `spell` to or remove it from each spellbook.
+    pub fn add_menu_ui(&mut self, ui: &mut egui::Ui, spell: &Spell) {
+        for book in &mut self.books {
+            let mut contained = book.contains(spell);
+            if ui.checkbox(&mut contained, &book.name).changed() {
+                if contained {
+                    book.add(spell);
+                } else {
+                    book.remove(spell);
+                }
+                self.filters_changed = true;
+            }
+        }
+        if ui.button("New spellbook").clicked() {
+            let mut book = Spellbook::new(self.unique_name());
+            book.add(spell);
+            self.books.push(book);
+            self.filters_changed = true;
+            ui.close_menu();
+        }
+    }
+
+    /// The spellbook row of the Sources window.
+    pub fn source_filter_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Spellbook");
+            egui::ComboBox::from_id_salt("spellbook_filter")
+                .selected_text(self.filter.as_deref().unwrap_or("All spells"))
+                .show_ui(ui, |ui| {
+                    let mut changed = ui
+                        .selectable_value(&mut self.filter, None, "All spells")
+                        .changed();
+                    for book in &self.books {
+                        changed |= ui
+                            .selectable_value(&mut self.filter, Some(book.name.clone()), &book.name)
+                            .changed();
+                    }
+                    if changed {
+                        self.filters_changed = true;
+                    }
+                });
+        });
+    }
+
+    fn unique_name(&self) -> String {
+        (1..)
+            .map(|i| format!("Spellbook {i}"))
+            .find(|name| self.books.iter().all(|b| b.name != *name))
+            .unwrap_or_default()
+    }
+}
+
+/// The summed material component costs of `spells`, like "1500 gp" or
+/// "1500 gp + 2 unknown" for costly components without a price.
+pub fn material_cost_summary<'a>(spells: impl IntoIterator<Item = &'a Spell>) -> String {
+    let mut total = 0;
+    let mut unknown = 0;
+    for spell in spells {
+        match spell.material_cost() {
+            Some(cost) => total += cost,
+            None => unknown += 1,
+        }
+    }
+    if unknown > 0 {
+        format!("{total} gp + {unknown} unknown")
+    } else {
+        format!("{total} gp")
+    }
+}
+
+fn spell_by_id(id: u32) -> Option<&'static Spell> {
+    ALL_SPELLS.iter().find(|spell| spell.id == id)
+}
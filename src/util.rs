@@ -1,21 +1,24 @@
-use crate::spell::{SpellDescriptionStruct, ALL_SPELLS};
+use crate::{
+    query::{MinArea, MinDuration, MinRange, SpellQuery},
+    spell::{SpellDescriptionStruct, TimeUnit, ALL_SPELLS},
+};
 
 #[macro_export]
 macro_rules! filter_row {
-    ($ui:ident, $filter_window:expr, $value:ident, $or:ident, $label:expr) => {
+    ($ui:ident, $query:expr, $value:ident, $or:ident, $changed:expr, $label:expr) => {
         $ui.horizontal(|ui| {
             if ($label != "") {
                 ui.label($label);
             }
-            if ui.add(toggle(&mut $filter_window.$or)).changed() {
-                $filter_window.filters_changed = true;
+            if ui.add(toggle(&mut $query.$or)).changed() {
+                $changed = true;
             }
         });
         $ui.horizontal_wrapped(|ui| {
-            for x in &mut $filter_window.$value {
+            for x in &mut $query.$value {
                 let resp = x.create_btn(ui);
                 if resp != *x {
-                    $filter_window.filters_changed = true;
+                    $changed = true;
                 }
                 *x = resp;
             }
@@ -63,13 +66,92 @@ pub fn toggle(on: &mut bool) -> impl egui::Widget + '_ {
     move |ui: &mut egui::Ui| toggle_ui(ui, on, "OR", "AND")
 }
 
+/// Editor for a minimum range, returns whether it was changed.
+pub fn min_range_ui(ui: &mut egui::Ui, range: &mut MinRange) -> bool {
+    let mut changed = ui
+        .add(
+            egui::DragValue::new(&mut range.feet)
+                .range(0..=100_000)
+                .suffix(" ft"),
+        )
+        .changed();
+    ui.label("at CL");
+    changed |= ui
+        .add(egui::DragValue::new(&mut range.caster_level).range(1..=20))
+        .changed();
+    changed
+}
+
+/// Editor for a minimum radius or length, returns whether it was changed.
+pub fn min_area_ui(ui: &mut egui::Ui, area: &mut MinArea) -> bool {
+    ui.add(
+        egui::DragValue::new(&mut area.feet)
+            .range(0..=1000)
+            .suffix(" ft"),
+    )
+    .changed()
+}
+
+/// Editor for a minimum duration, returns whether it was changed.
+pub fn min_duration_ui(ui: &mut egui::Ui, id: egui::Id, duration: &mut MinDuration) -> bool {
+    let mut changed = ui
+        .add(egui::DragValue::new(&mut duration.amount).range(1..=1000))
+        .changed();
+    egui::ComboBox::from_id_salt(id.with("unit"))
+        .selected_text(duration.unit.name())
+        .width(60.0)
+        .show_ui(ui, |ui| {
+            for unit in TimeUnit::get_all() {
+                changed |= ui
+                    .selectable_value(&mut duration.unit, unit, unit.name())
+                    .changed();
+            }
+        });
+    changed |= ui.checkbox(&mut duration.per_level, "/level").changed();
+    changed
+}
+
+/// Text in the default body style.
+pub fn body_format(ui: &egui::Ui) -> egui::TextFormat {
+    egui::TextFormat::simple(
+        egui::TextStyle::Body.resolve(ui.style()),
+        ui.visuals().text_color(),
+    )
+}
+
+/// Lays out `text` in `format`, with the parts matched by the query's keywords or name
+/// highlighted.
+pub fn highlight(
+    ui: &egui::Ui,
+    text: &str,
+    query: &SpellQuery,
+    format: egui::TextFormat,
+) -> egui::text::LayoutJob {
+    let marked = egui::TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        color: ui.visuals().selection.stroke.color,
+        ..format.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut end = 0;
+    for range in query.highlights(text) {
+        job.append(&text[end..range.start], 0.0, format.clone());
+        job.append(&text[range.clone()], 0.0, marked.clone());
+        end = range.end;
+    }
+    job.append(&text[end..], 0.0, format);
+    job
+}
+
+/// Renders a spell description, highlighting what `query` searched for.
 pub fn html2egui(
     html: &'static SpellDescriptionStruct,
     ui: &mut egui::Ui,
+    query: &SpellQuery,
 ) -> Option<(crate::spell::Spell, bool)> {
     let x = ui.spacing().item_spacing.x;
 
-    struct2egui(html, ui, x, false, 0)
+    struct2egui(html, ui, x, false, 0, query)
 }
 
 fn struct2egui(
@@ -78,6 +160,7 @@ fn struct2egui(
     x: f32,
     inline: bool,
     body_index: usize,
+    query: &SpellQuery,
 ) -> Option<(crate::spell::Spell, bool)> {
     match st {
         SpellDescriptionStruct::Body(spell_description_structs) => {
@@ -86,7 +169,7 @@ fn struct2egui(
                     ui.spacing_mut().item_spacing.x = 0.0;
                     let mut ret = None;
                     for (i, c) in spell_description_structs.iter().enumerate() {
-                        if let Some(res) = struct2egui(c, ui, x, false, i) {
+                        if let Some(res) = struct2egui(c, ui, x, false, i, query) {
                             ret = Some(res);
                         }
                     }
@@ -101,7 +184,7 @@ fn struct2egui(
             } else if inline {
                 let mut ret = None;
                 for (i, c) in spell_description_structs.iter().enumerate() {
-                    if let Some(res) = struct2egui(c, ui, x, inline, body_index * 10 + i) {
+                    if let Some(res) = struct2egui(c, ui, x, inline, body_index * 10 + i, query) {
                         ret = Some(res);
                     }
                 }
@@ -111,7 +194,8 @@ fn struct2egui(
                     ui.spacing_mut().item_spacing.x = 0.0;
                     let mut ret = None;
                     for (i, c) in spell_description_structs.iter().enumerate() {
-                        if let Some(res) = struct2egui(c, ui, x, inline, body_index * 10 + i) {
+                        if let Some(res) = struct2egui(c, ui, x, inline, body_index * 10 + i, query)
+                        {
                             ret = Some(res);
                         }
                     }
@@ -125,7 +209,7 @@ fn struct2egui(
             for c in spell_description_structs {
                 match c {
                     SpellDescriptionStruct::Text(text) => {
-                        ui.label(text);
+                        ui.label(highlight(ui, text, query, body_format(ui)));
                     }
                     _ => panic!("non text element found in caption"),
                 }
@@ -146,7 +230,11 @@ fn struct2egui(
                     false
                 }
             }) {
-                let res = ui.link(egui::RichText::new(text));
+                let format = egui::TextFormat {
+                    color: ui.visuals().hyperlink_color,
+                    ..body_format(ui)
+                };
+                let res = ui.link(highlight(ui, text, query, format));
                 if res.clicked() {
                     Some((spell.clone(), false))
                 } else if res.secondary_clicked() {
@@ -155,12 +243,20 @@ fn struct2egui(
                     None
                 }
             } else {
-                ui.label(egui::RichText::new(text).italics());
+                let format = egui::TextFormat {
+                    italics: true,
+                    ..body_format(ui)
+                };
+                ui.label(highlight(ui, text, query, format));
                 None
             }
         }
         SpellDescriptionStruct::Bold(text) => {
-            ui.label(egui::RichText::new(text).strong());
+            let format = egui::TextFormat {
+                color: ui.visuals().strong_text_color(),
+                ..body_format(ui)
+            };
+            ui.label(highlight(ui, text, query, format));
             None
         }
         SpellDescriptionStruct::Sup(spell_description_structs) => {
@@ -169,7 +265,7 @@ fn struct2egui(
                 .horizontal_top(|ui| {
                     let mut ret = None;
                     for c in spell_description_structs {
-                        if let Some(res) = struct2egui(c, ui, x, false, body_index) {
+                        if let Some(res) = struct2egui(c, ui, x, false, body_index, query) {
                             ret = Some(res);
                         }
                     }
@@ -193,7 +289,7 @@ fn struct2egui(
 
                     for c in spell_description_structs {
                         if let SpellDescriptionStruct::Line(_) = c {
-                            if let Some(res) = struct2egui(c, ui, x, true, body_index) {
+                            if let Some(res) = struct2egui(c, ui, x, true, body_index, query) {
                                 ret = Some(res);
                             }
                         } else {
@@ -218,7 +314,7 @@ fn struct2egui(
                     ui.spacing_mut().item_spacing.x = 0.0;
                     let mut ret = None;
                     for c in spell_description_structs {
-                        if let Some(res) = struct2egui(c, ui, x, true, body_index) {
+                        if let Some(res) = struct2egui(c, ui, x, true, body_index, query) {
                             ret = Some(res);
                         }
                     }
@@ -240,7 +336,14 @@ fn struct2egui(
                 .find(|e| matches!(e, SpellDescriptionStruct::Tbody(_)))
                 .unwrap_or(&SpellDescriptionStruct::Br);
 
-            struct2egui(&SpellDescriptionStruct::Br, ui, x, inline, body_index);
+            struct2egui(
+                &SpellDescriptionStruct::Br,
+                ui,
+                x,
+                inline,
+                body_index,
+                query,
+            );
             ui.label(" ");
 
             ui.with_layout(
@@ -256,7 +359,7 @@ fn struct2egui(
                                 .iter()
                                 .find(|e| matches!(e, SpellDescriptionStruct::Caption(_)))
                             {
-                                struct2egui(tcaption, ui, x, inline, body_index);
+                                struct2egui(tcaption, ui, x, inline, body_index, query);
                             };
 
                             egui::Frame::new()
@@ -273,17 +376,19 @@ fn struct2egui(
                                             egui::Direction::LeftToRight,
                                         ))
                                         .columns(egui_extras::Column::auto(), n);
-                                    let table = thead2egui(thead, tb, x);
+                                    let table = thead2egui(thead, tb, x, query);
 
                                     if let SpellDescriptionStruct::Tbody(tbody_rows) = tbody {
-                                        tbody2egui(tbody, table, tbodylen(tbody, n), x);
+                                        tbody2egui(tbody, table, tbodylen(tbody, n), x, query);
 
                                         if let Some(SpellDescriptionStruct::Row(cells)) =
                                             tbody_rows.last()
                                         {
                                             if cells.len() != n {
                                                 for cell in cells {
-                                                    struct2egui(cell, ui, x, false, body_index);
+                                                    struct2egui(
+                                                        cell, ui, x, false, body_index, query,
+                                                    );
                                                 }
                                             }
                                         }
@@ -296,7 +401,7 @@ fn struct2egui(
                                 .iter()
                                 .find(|e| matches!(e, SpellDescriptionStruct::Tfoot(_)))
                             {
-                                struct2egui(tfoot, ui, x, inline, body_index);
+                                struct2egui(tfoot, ui, x, inline, body_index, query);
                             };
                         },
                     );
@@ -312,7 +417,7 @@ fn struct2egui(
             ui.horizontal(|ui| {
                 let mut ret = None;
                 for c in spell_description_structs {
-                    if let Some(res) = struct2egui(c, ui, x, true, body_index) {
+                    if let Some(res) = struct2egui(c, ui, x, true, body_index, query) {
                         ret = Some(res);
                     }
                 }
@@ -326,7 +431,7 @@ fn struct2egui(
                 .horizontal(|ui| {
                     let mut ret = None;
                     for c in spell_description_structs {
-                        if let Some(res) = struct2egui(c, ui, x, true, body_index) {
+                        if let Some(res) = struct2egui(c, ui, x, true, body_index, query) {
                             ret = Some(res);
                         }
                     }
@@ -347,13 +452,13 @@ fn struct2egui(
                 })
                 .for_each(|c| {
                     for cc in c {
-                        struct2egui(cc, ui, x, true, body_index);
+                        struct2egui(cc, ui, x, true, body_index, query);
                     }
                 });
             None
         }
         SpellDescriptionStruct::Text(text) => {
-            ui.label(text);
+            ui.label(highlight(ui, text, query, body_format(ui)));
             None
         }
         _ => {
@@ -366,13 +471,14 @@ fn thead2egui<'a>(
     head: &'static SpellDescriptionStruct,
     tb: egui_extras::TableBuilder<'a>,
     x: f32,
+    query: &SpellQuery,
 ) -> egui_extras::Table<'a> {
     if let SpellDescriptionStruct::Thead(header_data) = head {
         if header_data.len() != 1 {
             tb.header(20.0, |_| {})
         } else if let Some(head_row) = header_data.first() {
             tb.header(20.0, |row| {
-                trow2egui(head_row, row, x);
+                trow2egui(head_row, row, x, query);
             })
         } else {
             tb.header(20.0, |_| {})
@@ -387,11 +493,12 @@ fn tbody2egui(
     t: egui_extras::Table<'_>,
     nrows: usize,
     x: f32,
+    query: &SpellQuery,
 ) -> egui::scroll_area::ScrollAreaOutput<()> {
     if let SpellDescriptionStruct::Tbody(body_rows) = body_data {
         t.body(|body| {
             body.rows(20.0, nrows, |row| {
-                trow2egui(body_rows.get(row.index()).unwrap(), row, x);
+                trow2egui(body_rows.get(row.index()).unwrap(), row, x, query);
             });
         })
     } else {
@@ -403,17 +510,18 @@ fn trow2egui(
     row_data: &'static SpellDescriptionStruct,
     mut row: egui_extras::TableRow<'_, '_>,
     x: f32,
+    query: &SpellQuery,
 ) {
     if let SpellDescriptionStruct::Row(cells) = row_data {
         for cell in cells {
             row.col(|ui| {
-                cell2egui(cell, ui, x);
+                cell2egui(cell, ui, x, query);
             });
         }
     }
 }
 
-fn cell2egui(cell: &'static SpellDescriptionStruct, ui: &mut egui::Ui, x: f32) {
+fn cell2egui(cell: &'static SpellDescriptionStruct, ui: &mut egui::Ui, x: f32, query: &SpellQuery) {
     egui::Frame::new()
         .outer_margin(egui::Margin::symmetric(-4, -2))
         .inner_margin(0)
@@ -422,7 +530,7 @@ fn cell2egui(cell: &'static SpellDescriptionStruct, ui: &mut egui::Ui, x: f32) {
             ui.set_width(ui.max_rect().width());
             ui.horizontal_centered(|ui| {
                 ui.label("");
-                struct2egui(cell, ui, x, true, 0);
+                struct2egui(cell, ui, x, true, 0, query);
                 ui.label("");
             });
         });
@@ -451,3 +559,44 @@ fn rowlen(telem: &'static SpellDescriptionStruct) -> usize {
         _ => 0,
     }
 }
+
+/// Percent encodes `text` for use in a URL fragment, spaces become `+`.
+pub fn url_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for b in text.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b':' | b',' => {
+                encoded.push(b as char)
+            }
+            b' ' => encoded.push('+'),
+            b => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Reverses [`url_encode`], invalid escapes are kept as they are.
+#[cfg(target_arch = "wasm32")]
+pub fn url_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match text
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(b) => {
+                    decoded.push(b);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}